@@ -70,11 +70,12 @@ func _ready():
 		livekit_manager.room_disconnected.connect(_on_room_disconnected)
 		livekit_manager.participant_joined.connect(_on_participant_joined)
 		livekit_manager.participant_left.connect(_on_participant_left)
-		livekit_manager.on_audio_frame.connect(_on_audio_frame)
 		livekit_manager.chat_message_received.connect(_on_chat_message_received)
 		livekit_manager.participant_name_changed.connect(_on_participant_name_changed)
 		livekit_manager.error_occurred.connect(_on_error)
 		
+		# Playback and levels come from Rust, so the per-frame on_audio_frame signal stays off
+		
 		# Set sample rate
 		var mix_rate = AudioServer.get_mix_rate()
 		livekit_manager.set_mic_sample_rate(int(mix_rate))
@@ -155,6 +156,9 @@ func _ready():
 	disconnect_button.disabled = true
 	threshold_slider.value = mic_threshold
 	_on_threshold_changed(mic_threshold)
+	# Let the threshold slider gate what gets sent
+	if livekit_manager:
+		livekit_manager.set_voice_activation_mode(livekit_manager.VOICE_ACTIVITY)
 	
 	# Set local server values for easy testing
 	server_entry.text = "ws://localhost:7880"
@@ -284,6 +288,9 @@ func _process(delta):
 	# Update participant levels
 	for p_id in participants:
 		var p_data = participants[p_id]
+		if livekit_manager:
+			var level = livekit_manager.get_participant_audio_level(p_id)
+			p_data["level"] = max(p_data["level"], level["peak"])
 		if p_data.has("level_bar") and p_data["level_bar"]:
 			p_data["level_bar"].value = p_data["level"] * 100
 			# Decay level
@@ -296,8 +303,8 @@ func _process_mic_audio():
 		# Audio is already amplified by AudioEffectAmplify on the bus
 		# No need for additional software gain here
 		
-		# Only push to LiveKit if connected and not muted
-		if livekit_manager and livekit_manager.is_room_connected() and not is_muted:
+		# Muting is handled on the published track, so keep pushing
+		if livekit_manager and livekit_manager.is_room_connected():
 			livekit_manager.push_mic_audio(buffer)
 		
 		# Visualize level
@@ -360,9 +367,13 @@ func _on_room_connected():
 	# Add local participant
 	_add_participant("You (local)", 0.0)
 
-func _on_room_disconnected():
-	print("📴 Disconnected")
+func _on_room_disconnected(reason: int):
+	print("📴 Disconnected (reason ", reason, ")")
 	status_label.text = "Disconnected"
+	if reason == livekit_manager.ERROR_KICKED:
+		status_label.text = "Removed from room"
+	elif reason == livekit_manager.ERROR_DUPLICATE_IDENTITY:
+		status_label.text = "Joined from another device"
 	connect_button.disabled = false
 	disconnect_button.disabled = true
 	
@@ -374,6 +385,8 @@ func _on_room_disconnected():
 func _on_participant_joined(identity: String):
 	print("👤 Participant joined: ", identity)
 	_add_participant(identity, 0.0)
+	# The player stays silent until the participant's voice is subscribed
+	_create_participant_audio(identity)
 	_update_participant_list()
 
 func _on_participant_left(identity: String):
@@ -381,47 +394,11 @@ func _on_participant_left(identity: String):
 	if participants.has(identity):
 		var p_data = participants[identity]
 		if p_data and p_data.get("player"):
+			livekit_manager.detach_participant_player(identity)
 			p_data["player"].queue_free()
 		participants.erase(identity)
 		_update_participant_list()
 
-func _on_audio_frame(peer_id: String, frame: PackedVector2Array):
-	# Ensure participant exists in dictionary
-	if not participants.has(peer_id):
-		_add_participant(peer_id, 0.0)
-		_update_participant_list()
-	
-	var p_data = participants[peer_id]
-	
-	# Create audio player if needed
-	if p_data["player"] == null:
-		_create_participant_audio(peer_id)
-		p_data = participants[peer_id] # Refresh ref
-	
-	# Calculate level
-	var max_amp = 0.0
-	for sample in frame:
-		var amp = max(abs(sample.x), abs(sample.y))
-		max_amp = max(max_amp, amp)
-	
-	# Update level (keep max for visibility)
-	p_data["level"] = max(p_data["level"], max_amp)
-
-	var player = p_data["player"]
-	if player and not p_data["muted"]:
-		var playback = player.get_stream_playback()
-		if playback:
-			# Apply volume scaling
-			var vol = p_data.get("volume", 1.0)
-			if vol != 1.0:
-				var scaled_frame = PackedVector2Array()
-				scaled_frame.resize(frame.size())
-				for i in range(frame.size()):
-					scaled_frame[i] = frame[i] * vol
-				playback.push_buffer(scaled_frame)
-			else:
-				playback.push_buffer(frame)
-
 func _create_participant_audio(peer_id: String):
 	# Only create if we don't already have a player for this participant
 	if not participants.has(peer_id):
@@ -437,13 +414,15 @@ func _create_participant_audio(peer_id: String):
 		player.autoplay = true
 		add_child(player)
 		player.play()
+		livekit_manager.attach_participant_player(peer_id, player)
 		
 		p_data["player"] = player
+		_apply_participant_volume(peer_id)
 		print("   Created audio player for: ", peer_id)
 		_update_participant_list()
 
-func _on_error(msg: String):
-	print("❌ Error: ", msg)
+func _on_error(code: int, msg: String):
+	print("❌ Error (", code, "): ", msg)
 	status_label.text = "Error: " + msg
 	connect_button.disabled = false
 
@@ -452,14 +431,18 @@ func _on_mute_toggle():
 	is_muted = !is_muted
 	mute_button.text = "🔇 Muted" if is_muted else "🎤 Active"
 	
-	# We don't stop the player so we can still see visualization if we wanted,
-	# but for now let's just stop pushing audio in _process_mic_audio.
+	# Mutes the published track so other clients see the mute
+	if livekit_manager:
+		livekit_manager.set_microphone_muted(is_muted)
+	
 	# Also update visualizer color
 	mic_level_bar.modulate = Color.GRAY if is_muted else Color.WHITE
 
 func _on_threshold_changed(value: float):
 	mic_threshold = value
 	threshold_label.text = "%.2f" % mic_threshold
+	if livekit_manager:
+		livekit_manager.set_vad_threshold_db(linear_to_db(maxf(mic_threshold, 0.0001)))
 
 func _on_hear_audio_toggled(button_pressed: bool):
 	hear_own_audio = button_pressed
@@ -616,6 +599,7 @@ func _update_participant_list():
 func _on_participant_volume_changed(value: float, participant_id: String):
 	if participants.has(participant_id):
 		participants[participant_id]["volume"] = value
+		_apply_participant_volume(participant_id)
 		print("Volume for ", participant_id, " set to ", value)
 
 func _on_participant_mute_toggled(participant_id: String, btn: Button):
@@ -623,8 +607,15 @@ func _on_participant_mute_toggled(participant_id: String, btn: Button):
 		var p_data = participants[participant_id]
 		p_data["muted"] = !p_data["muted"]
 		btn.text = "🔇" if p_data["muted"] else "🔊"
+		_apply_participant_volume(participant_id)
 		print("Toggled mute for ", participant_id, ": ", p_data["muted"])
 
+func _apply_participant_volume(participant_id: String):
+	var p_data = participants[participant_id]
+	var player = p_data["player"]
+	if player:
+		player.volume_db = -80.0 if p_data["muted"] else linear_to_db(maxf(p_data["volume"], 0.0001))
+
 
 # Chat and Username handlers
 func _on_chat_message_received(sender: String, message: String, timestamp: int):
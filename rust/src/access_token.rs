@@ -0,0 +1,126 @@
+use godot::prelude::*;
+use livekit_api::access_token::{AccessToken, Claims, VideoGrants};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Mints room tokens from GDScript for the editor and local testing, e.g.
+/// with the `devkey` / `secret` pair from `livekit.yaml`. Production tokens
+/// should come from a server that keeps the secret.
+#[derive(GodotClass)]
+#[class(base=RefCounted)]
+pub struct LiveKitAccessToken {
+    base: Base<RefCounted>,
+
+    #[var]
+    api_key: GString,
+    #[var]
+    api_secret: GString,
+    /// Participant identity; must match the game's user id for positional
+    /// voice.
+    #[var]
+    identity: GString,
+    /// Display name shown to other participants.
+    #[var]
+    name: GString,
+    #[var]
+    metadata: GString,
+    /// Room the token may join.
+    #[var]
+    room: GString,
+    /// Seconds until the token expires.
+    #[var]
+    ttl_seconds: i64,
+    #[var]
+    can_publish: bool,
+    #[var]
+    can_subscribe: bool,
+    #[var]
+    can_publish_data: bool,
+    /// Join without being listed to other participants.
+    #[var]
+    hidden: bool,
+}
+
+#[godot_api]
+impl IRefCounted for LiveKitAccessToken {
+    fn init(base: Base<RefCounted>) -> Self {
+        Self {
+            base,
+            api_key: GString::new(),
+            api_secret: GString::new(),
+            identity: GString::new(),
+            name: GString::new(),
+            metadata: GString::new(),
+            room: GString::new(),
+            ttl_seconds: 6 * 3600,
+            can_publish: true,
+            can_subscribe: true,
+            can_publish_data: true,
+            hidden: false,
+        }
+    }
+}
+
+#[godot_api]
+impl LiveKitAccessToken {
+    /// Signs the token. Returns an empty string and logs why if the key,
+    /// secret, identity or room is missing.
+    #[func]
+    pub fn to_jwt(&self) -> GString {
+        let grants = VideoGrants {
+            room_join: true,
+            room: self.room.to_string(),
+            can_publish: self.can_publish,
+            can_subscribe: self.can_subscribe,
+            can_publish_data: self.can_publish_data,
+            hidden: self.hidden,
+            ..Default::default()
+        };
+
+        let token =
+            AccessToken::with_api_key(&self.api_key.to_string(), &self.api_secret.to_string())
+                .with_identity(&self.identity.to_string())
+                .with_name(&self.name.to_string())
+                .with_metadata(&self.metadata.to_string())
+                .with_ttl(Duration::from_secs(self.ttl_seconds.max(1) as u64))
+                .with_grants(grants);
+
+        match token.to_jwt() {
+            Ok(jwt) => GString::from(jwt),
+            Err(e) => {
+                godot_error!("LiveKit: Failed to create access token: {}", e);
+                GString::new()
+            }
+        }
+    }
+
+    /// Reads a token's claims without checking its signature, to show who
+    /// and where it is for and when it expires. Returns `identity`, `name`,
+    /// `room`, `metadata`, `issuer`, `not_before` and `expires_at` (Unix
+    /// seconds) and `expired`, or an empty dictionary if it isn't a token.
+    #[func]
+    pub fn decode_token_claims(token: GString) -> Dictionary {
+        let mut claims = Dictionary::new();
+        let decoded = match Claims::from_unverified(token.to_string().trim()) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                godot_warn!("LiveKit: Failed to decode access token: {}", e);
+                return claims;
+            }
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        claims.set("identity", decoded.sub);
+        claims.set("name", decoded.name);
+        claims.set("room", decoded.video.room);
+        claims.set("metadata", decoded.metadata);
+        claims.set("issuer", decoded.iss);
+        claims.set("not_before", decoded.nbf as i64);
+        claims.set("expires_at", decoded.exp as i64);
+        claims.set("expired", (decoded.exp as u64) <= now);
+        claims
+    }
+}
@@ -1,76 +1,93 @@
 use godot::prelude::*;
-use godot::classes::{AudioEffectCapture, AudioServer, AudioStreamGeneratorPlayback};
+use godot::classes::{AudioEffectCapture, AudioServer};
 
 
 pub struct AudioHandler {
     capture_effect: Option<Gd<AudioEffectCapture>>,
-    sample_rate: i32,
 }
 
 impl AudioHandler {
     pub fn new() -> Self {
         Self {
             capture_effect: None,
-            sample_rate: 48000, // LiveKit uses 48kHz
         }
     }
 
-    pub fn init_capture(&mut self, bus_index: i32) {
+    /// Finds or creates a muted bus named `bus_name` with an `AudioEffectCapture`
+    /// on it and returns its index. Muting keeps the mic from being monitored
+    /// through the speakers while the capture effect still sees the audio.
+    pub fn setup_capture_bus(bus_name: &str) -> i32 {
         let mut audio_server = AudioServer::singleton();
-        
-        if let Some(effect) = audio_server.get_bus_effect(bus_index, 0) {
-            if let Ok(capture) = effect.try_cast::<AudioEffectCapture>() {
-                self.capture_effect = Some(capture);
-                godot_print!("Audio capture initialized");
+
+        let mut bus_index = audio_server.get_bus_index(bus_name);
+        if bus_index < 0 {
+            audio_server.add_bus();
+            bus_index = audio_server.get_bus_count() - 1;
+            audio_server.set_bus_name(bus_index, bus_name);
+            godot_print!("Created audio bus '{}'", bus_name);
+        }
+        audio_server.set_bus_mute(bus_index, true);
+
+        Self::add_capture_effect(bus_index);
+        bus_index
+    }
+
+    /// Adds an `AudioEffectCapture` to the bus at `bus_index` unless it already
+    /// has one. The bus keeps playing as before.
+    pub fn add_capture_effect(bus_index: i32) {
+        let mut audio_server = AudioServer::singleton();
+
+        let has_capture = (0..audio_server.get_bus_effect_count(bus_index)).any(|i| {
+            audio_server
+                .get_bus_effect(bus_index, i)
+                .is_some_and(|effect| effect.try_cast::<AudioEffectCapture>().is_ok())
+        });
+        if !has_capture {
+            audio_server.add_bus_effect(bus_index, &AudioEffectCapture::new_gd());
+        }
+    }
+
+    pub fn init_capture(&mut self, bus_index: i32) -> bool {
+        let mut audio_server = AudioServer::singleton();
+
+        for i in 0..audio_server.get_bus_effect_count(bus_index) {
+            if let Some(effect) = audio_server.get_bus_effect(bus_index, i) {
+                if let Ok(capture) = effect.try_cast::<AudioEffectCapture>() {
+                    self.capture_effect = Some(capture);
+                    godot_print!("Audio capture initialized");
+                    return true;
+                }
             }
         }
+        godot_warn!("No AudioEffectCapture found on bus {}", bus_index);
+        false
     }
 
-    /// Capture audio from microphone and return as PCM samples
+    /// Whether both handlers read from the same `AudioEffectCapture`. Each
+    /// read drains the effect, so two of them would split the audio.
+    pub fn shares_capture_with(&self, other: &AudioHandler) -> bool {
+        matches!((&self.capture_effect, &other.capture_effect), (Some(a), Some(b)) if a == b)
+    }
+
+    /// Capture audio from microphone and return it as interleaved stereo PCM
     pub fn capture_microphone_audio(&mut self) -> Option<Vec<f32>> {
         if let Some(capture) = &mut self.capture_effect {
             let frames_available = capture.get_frames_available();
-            
+
             if frames_available > 0 {
-                let buffer_size = (frames_available * 2) as usize; // Stereo
-                let mut samples = Vec::with_capacity(buffer_size);
-                
-                for _ in 0..frames_available {
-                    let frame = capture.get_buffer(1);
-                    let frame_data = frame.as_slice();
-                    for sample in frame_data {
-                        let mono = (sample.x + sample.y) / 2.0;
-                        samples.push(mono);
-                    }
-                }
-                
+                // Read everything queued in one call; the feeder mixes down if needed
+                let buffer = capture.get_buffer(frames_available);
+                let samples = buffer
+                    .as_slice()
+                    .iter()
+                    .flat_map(|frame| [frame.x, frame.y])
+                    .collect();
+
                 return Some(samples);
             }
         }
         None
     }
-
-    /// Write PCM samples to AudioStreamGeneratorPlayback
-    pub fn write_to_playback(
-        &self,
-        playback: &mut Gd<AudioStreamGeneratorPlayback>,
-        samples: &[f32],
-    ) {
-        // Convert mono to stereo for Godot
-        let mut stereo_samples = Vec::with_capacity(samples.len() * 2);
-        for &sample in samples {
-            stereo_samples.push(Vector2::new(sample, sample));
-        }
-
-        // Push frames to playback buffer
-        for frame in stereo_samples {
-            playback.push_frame(frame);
-        }
-    }
-
-    pub fn get_sample_rate(&self) -> i32 {
-        self.sample_rate
-    }
 }
 
 impl Default for AudioHandler {
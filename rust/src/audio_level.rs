@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Linear RMS and peak (0..1) of the most recent 10ms frame, written by an
+/// audio task and read from the main thread.
+#[derive(Default)]
+pub struct AudioLevel {
+    rms: AtomicU32,
+    peak: AtomicU32,
+}
+
+impl AudioLevel {
+    pub fn update(&self, samples: &[i16]) {
+        let (rms, peak) = measure(samples);
+        self.store(rms, peak);
+    }
+
+    pub fn store(&self, rms: f32, peak: f32) {
+        self.rms.store(rms.to_bits(), Ordering::Relaxed);
+        self.peak.store(peak.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        self.store(0.0, 0.0);
+    }
+
+    pub fn rms(&self) -> f32 {
+        f32::from_bits(self.rms.load(Ordering::Relaxed))
+    }
+
+    pub fn peak(&self) -> f32 {
+        f32::from_bits(self.peak.load(Ordering::Relaxed))
+    }
+}
+
+/// Levels of remote participants, keyed by identity.
+#[derive(Clone, Default)]
+pub struct AudioLevels(Arc<Mutex<HashMap<String, Arc<AudioLevel>>>>);
+
+impl AudioLevels {
+    /// Returns the level slot for `identity`, creating it if needed.
+    pub fn slot(&self, identity: &str) -> Arc<AudioLevel> {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(identity.to_string())
+            .or_default()
+            .clone()
+    }
+
+    pub fn get(&self, identity: &str) -> Option<Arc<AudioLevel>> {
+        self.0.lock().unwrap().get(identity).cloned()
+    }
+
+    pub fn remove(&self, identity: &str) {
+        self.0.lock().unwrap().remove(identity);
+    }
+
+    /// Copies out every participant's current (rms, peak).
+    pub fn snapshot(&self) -> Vec<(String, f32, f32)> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(identity, level)| (identity.clone(), level.rms(), level.peak()))
+            .collect()
+    }
+}
+
+/// Linear RMS and peak of `samples`, both 0 for an empty frame.
+pub fn measure(samples: &[i16]) -> (f32, f32) {
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    let mut sum = 0.0f64;
+    let mut peak = 0.0f32;
+    for &sample in samples {
+        let f = sample as f32 / 32768.0;
+        sum += (f * f) as f64;
+        peak = peak.max(f.abs());
+    }
+    (((sum / samples.len() as f64).sqrt()) as f32, peak)
+}
@@ -0,0 +1,153 @@
+use godot::prelude::*;
+use livekit::options::{self, AudioEncoding, TrackPublishOptions};
+use livekit::track::TrackSource;
+use livekit::webrtc::{audio_source::native::NativeAudioSource, prelude::AudioSourceOptions};
+
+/// Rate published audio sources run at.
+pub const PUBLISH_SAMPLE_RATE: u32 = 48000;
+
+/// Settings a published audio track is created and published with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AudioTrackOptions {
+    pub echo_cancellation: bool,
+    pub noise_suppression: bool,
+    pub auto_gain_control: bool,
+    pub queue_size_ms: u32,
+    pub channels: u32,
+    pub dtx: bool,
+    pub red: bool,
+    /// 0 picks a preset for the channel count.
+    pub max_bitrate: u32,
+}
+
+impl Default for AudioTrackOptions {
+    fn default() -> Self {
+        Self {
+            echo_cancellation: true,
+            noise_suppression: true,
+            auto_gain_control: true,
+            queue_size_ms: 1000,
+            channels: 1,
+            dtx: true,
+            red: true,
+            max_bitrate: 0,
+        }
+    }
+}
+
+impl AudioTrackOptions {
+    /// Defaults for game audio such as music: stereo, no voice processing, no DTX.
+    pub fn music() -> Self {
+        Self {
+            echo_cancellation: false,
+            noise_suppression: false,
+            auto_gain_control: false,
+            channels: 2,
+            dtx: false,
+            ..Self::default()
+        }
+    }
+
+    pub fn create_source(&self) -> NativeAudioSource {
+        NativeAudioSource::new(
+            AudioSourceOptions {
+                echo_cancellation: self.echo_cancellation,
+                noise_suppression: self.noise_suppression,
+                auto_gain_control: self.auto_gain_control,
+            },
+            PUBLISH_SAMPLE_RATE,
+            self.channels,
+            self.queue_size_ms,
+        )
+    }
+
+    pub fn publish_options(&self, source: TrackSource) -> TrackPublishOptions {
+        let max_bitrate = match self.max_bitrate {
+            0 if self.channels > 1 => options::audio::MUSIC_STEREO.encoding.max_bitrate,
+            0 => options::audio::MUSIC.encoding.max_bitrate,
+            bitrate => bitrate as u64,
+        };
+
+        TrackPublishOptions {
+            audio_encoding: Some(AudioEncoding { max_bitrate }),
+            dtx: self.dtx,
+            red: self.red,
+            source,
+            ..Default::default()
+        }
+    }
+}
+
+/// Processing, buffering and encoding for a published audio track.
+///
+/// Pass it to `LiveKitManager.set_microphone_options()` or
+/// `LiveKitManager.publish_audio_bus()`. Turn echo cancellation off for
+/// headphone-only setups or when the game runs its own DSP. For music, use
+/// two channels and turn off DTX.
+#[derive(GodotClass)]
+#[class(base=Resource)]
+pub struct LiveKitAudioOptions {
+    base: Base<Resource>,
+
+    #[export]
+    echo_cancellation: bool,
+    #[export]
+    noise_suppression: bool,
+    #[export]
+    auto_gain_control: bool,
+    /// Audio buffered inside the source before it is encoded.
+    #[export]
+    queue_size_ms: i32,
+    /// 1 for mono, 2 for stereo.
+    #[export(range = (1.0, 2.0))]
+    channels: i32,
+    /// Discontinuous transmission: send almost nothing during silence.
+    #[export]
+    dtx: bool,
+    /// Redundant encoding, trading bandwidth for resilience to packet loss.
+    #[export]
+    red: bool,
+    /// Encoder bitrate cap in bits per second; 0 picks one for the channel count.
+    #[export]
+    max_bitrate: i32,
+}
+
+#[godot_api]
+impl IResource for LiveKitAudioOptions {
+    fn init(base: Base<Resource>) -> Self {
+        Self::with_options(base, AudioTrackOptions::default())
+    }
+}
+
+impl LiveKitAudioOptions {
+    fn with_options(base: Base<Resource>, options: AudioTrackOptions) -> Self {
+        Self {
+            base,
+            echo_cancellation: options.echo_cancellation,
+            noise_suppression: options.noise_suppression,
+            auto_gain_control: options.auto_gain_control,
+            queue_size_ms: options.queue_size_ms as i32,
+            channels: options.channels as i32,
+            dtx: options.dtx,
+            red: options.red,
+            max_bitrate: options.max_bitrate as i32,
+        }
+    }
+
+    pub(crate) fn track_options(&self) -> AudioTrackOptions {
+        AudioTrackOptions {
+            echo_cancellation: self.echo_cancellation,
+            noise_suppression: self.noise_suppression,
+            auto_gain_control: self.auto_gain_control,
+            queue_size_ms: self.queue_size_ms.max(10) as u32,
+            channels: self.channels.clamp(1, 2) as u32,
+            dtx: self.dtx,
+            red: self.red,
+            max_bitrate: self.max_bitrate.max(0) as u32,
+        }
+    }
+
+    pub(crate) fn from_track_options(options: AudioTrackOptions) -> Gd<Self> {
+        Gd::from_init_fn(|base| Self::with_options(base, options))
+    }
+}
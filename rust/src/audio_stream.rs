@@ -0,0 +1,129 @@
+use godot::prelude::*;
+use godot::classes::native::AudioFrame;
+use godot::classes::{
+    AudioServer, AudioStream, AudioStreamPlayback, IAudioStream, IAudioStreamPlayback,
+};
+use std::sync::Arc;
+
+use crate::remote_audio::{audio_channel, RemoteAudioReceiver, RemoteAudioSink, RemoteAudioSinks};
+use crate::resampler::ResamplerQuality;
+
+/// Ring buffer size per playback; the jitter buffer targets at most half of it.
+const MAX_LATENCY_MS: u32 = 400;
+
+/// An `AudioStream` that plays one participant's track. Works with any
+/// Godot player; samples are pulled on the audio thread as it mixes.
+///
+/// Create it with `LiveKitManager.create_participant_stream(identity)` for
+/// the participant's voice, or `create_participant_track_stream(identity,
+/// track_name)` for another audio track they publish.
+#[derive(GodotClass)]
+#[class(base=AudioStream, init)]
+pub struct LiveKitAudioStream {
+    base: Base<AudioStream>,
+
+    #[var]
+    participant_id: GString,
+    /// Empty for the participant's microphone.
+    #[var]
+    track_name: GString,
+
+    sinks: Option<RemoteAudioSinks>,
+    #[init(val = ResamplerQuality::Cubic)]
+    quality: ResamplerQuality,
+}
+
+impl LiveKitAudioStream {
+    pub(crate) fn new_for(
+        participant_id: GString,
+        track_name: GString,
+        sinks: RemoteAudioSinks,
+        quality: ResamplerQuality,
+    ) -> Gd<Self> {
+        Gd::from_init_fn(|base| Self {
+            base,
+            participant_id,
+            track_name,
+            sinks: Some(sinks),
+            quality,
+        })
+    }
+}
+
+#[godot_api]
+impl IAudioStream for LiveKitAudioStream {
+    fn instantiate_playback(&self) -> Option<Gd<AudioStreamPlayback>> {
+        let Some(sinks) = &self.sinks else {
+            godot_error!("LiveKitAudioStream: create it with LiveKitManager.create_participant_stream()");
+            return None;
+        };
+
+        // Each playback gets its own ring buffer; the newest one receives the track.
+        // mix() is called at the output rate, so resample straight to it.
+        let mix_rate = AudioServer::singleton().get_mix_rate() as u32;
+        let (sink, receiver) = audio_channel(MAX_LATENCY_MS, mix_rate, self.quality);
+        sinks.register(&self.participant_id.to_string(), &self.track_name.to_string(), &sink);
+
+        let playback = Gd::from_init_fn(|base| LiveKitAudioStreamPlayback {
+            base,
+            _sink: sink,
+            receiver,
+            playing: false,
+        });
+        Some(playback.upcast())
+    }
+
+    fn get_stream_name(&self) -> GString {
+        if self.track_name.is_empty() {
+            GString::from(format!("LiveKit: {}", self.participant_id))
+        } else {
+            GString::from(format!("LiveKit: {} ({})", self.participant_id, self.track_name))
+        }
+    }
+
+    fn get_length(&self) -> f64 {
+        0.0
+    }
+
+    fn is_monophonic(&self) -> bool {
+        true
+    }
+}
+
+/// Playback for `LiveKitAudioStream`, mixing straight out of the ring buffer.
+#[derive(GodotClass)]
+#[class(base=AudioStreamPlayback, no_init)]
+pub struct LiveKitAudioStreamPlayback {
+    base: Base<AudioStreamPlayback>,
+
+    // Keeps the registered sink alive for as long as this playback exists
+    _sink: Arc<RemoteAudioSink>,
+    receiver: RemoteAudioReceiver,
+    playing: bool,
+}
+
+#[godot_api]
+impl IAudioStreamPlayback for LiveKitAudioStreamPlayback {
+    fn start(&mut self, _from_pos: f64) {
+        self.playing = true;
+    }
+
+    fn stop(&mut self) {
+        self.playing = false;
+    }
+
+    fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    unsafe fn mix(&mut self, buffer: *mut AudioFrame, _rate_scale: f32, frames: i32) -> i32 {
+        if buffer.is_null() || frames <= 0 {
+            return 0;
+        }
+
+        // SAFETY: Godot hands us a buffer of exactly `frames` frames to fill
+        let out = std::slice::from_raw_parts_mut(buffer, frames as usize);
+        self.receiver.mix(out);
+        frames
+    }
+}
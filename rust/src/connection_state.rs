@@ -0,0 +1,80 @@
+use std::sync::{Arc, Mutex};
+
+/// Lifecycle of a `LiveKitManager` room session. The discriminants are the
+/// values of the `STATE_*` constants exposed to GDScript.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionState {
+    Disconnected = 0,
+    Connecting = 1,
+    Connected = 2,
+    Reconnecting = 3,
+    Disconnecting = 4,
+    Failed = 5,
+}
+
+impl ConnectionState {
+    /// Whether a session task is alive (or about to be) for this state.
+    pub fn is_active(self) -> bool {
+        matches!(
+            self,
+            Self::Connecting | Self::Connected | Self::Reconnecting | Self::Disconnecting
+        )
+    }
+}
+
+/// Connection state shared between the node and its session task.
+#[derive(Clone)]
+pub struct SharedConnectionState(Arc<Mutex<ConnectionState>>);
+
+impl SharedConnectionState {
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(ConnectionState::Disconnected)))
+    }
+
+    pub fn get(&self) -> ConnectionState {
+        *self.0.lock().unwrap()
+    }
+
+    /// Moves to `new`, returning the previous state if anything changed.
+    ///
+    /// Once a disconnect has been requested the only way out of
+    /// `Disconnecting` is `Disconnected`, so a connect that completes while
+    /// the user is tearing the session down can't flip the state back.
+    pub fn transition(&self, new: ConnectionState) -> Option<ConnectionState> {
+        let mut state = self.0.lock().unwrap();
+        let old = *state;
+        if old == new {
+            return None;
+        }
+        if old == ConnectionState::Disconnecting && new != ConnectionState::Disconnected {
+            return None;
+        }
+        *state = new;
+        Some(old)
+    }
+
+    /// Moves to the state a finished session task ends in, returning the
+    /// previous and new state if anything changed. `end` is replaced by
+    /// `Disconnected` if a disconnect was requested meanwhile, since nothing
+    /// else would move the state out of `Disconnecting`.
+    pub fn finish(&self, end: ConnectionState) -> Option<(ConnectionState, ConnectionState)> {
+        let mut state = self.0.lock().unwrap();
+        let old = *state;
+        let new = if old == ConnectionState::Disconnecting {
+            ConnectionState::Disconnected
+        } else {
+            end
+        };
+        if old == new {
+            return None;
+        }
+        *state = new;
+        Some((old, new))
+    }
+}
+
+impl Default for SharedConnectionState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,99 @@
+use livekit::{rtc_engine::EngineError, DisconnectReason, RoomError};
+use livekit_api::signal_client::SignalError;
+
+/// Why a connection failed or ended. Shared by `error_occurred(code, message)`
+/// and `room_disconnected(reason)`; the discriminants are the values of the
+/// `ERROR_*` constants exposed to GDScript.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// No error, e.g. `room_disconnected` after `disconnect_from_room`.
+    None = 0,
+    Unknown = 1,
+    ConnectFailed = 2,
+    TokenExpired = 3,
+    Unauthorized = 4,
+    RoomFull = 5,
+    Kicked = 6,
+    DuplicateIdentity = 7,
+    SignalTimeout = 8,
+    ConnectionLost = 9,
+    ServerShutdown = 10,
+    RoomClosed = 11,
+    PublishFailed = 12,
+    ReconnectFailed = 13,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 14] = [
+        Self::None,
+        Self::Unknown,
+        Self::ConnectFailed,
+        Self::TokenExpired,
+        Self::Unauthorized,
+        Self::RoomFull,
+        Self::Kicked,
+        Self::DuplicateIdentity,
+        Self::SignalTimeout,
+        Self::ConnectionLost,
+        Self::ServerShutdown,
+        Self::RoomClosed,
+        Self::PublishFailed,
+        Self::ReconnectFailed,
+    ];
+
+    pub fn from_i32(code: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| *c as i32 == code)
+    }
+
+    /// Whether connecting again with the same token can be expected to work.
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            Self::Unknown
+                | Self::ConnectFailed
+                | Self::SignalTimeout
+                | Self::ConnectionLost
+                | Self::ServerShutdown
+        )
+    }
+
+    /// Classifies a failed `Room::connect`. Join rejections arrive as the
+    /// HTTP status of the server's validate request; only a 401's message
+    /// tells an expired token apart from an invalid one.
+    pub fn from_connect_error(error: &RoomError) -> Self {
+        match error {
+            RoomError::Engine(EngineError::Signal(SignalError::Client(status, message))) => {
+                match status.as_u16() {
+                    401 if message.to_lowercase().contains("expired") => Self::TokenExpired,
+                    401 | 403 => Self::Unauthorized,
+                    429 => Self::RoomFull,
+                    _ => Self::ConnectFailed,
+                }
+            }
+            RoomError::Engine(EngineError::Signal(SignalError::TokenFormat)) => Self::Unauthorized,
+            RoomError::Engine(EngineError::Signal(SignalError::Timeout(_))) => Self::SignalTimeout,
+            // livekit doesn't re-export the protocol enum, so go by its proto name
+            RoomError::Request { reason, .. } => match reason.as_str_name() {
+                "LIMIT_EXCEEDED" => Self::RoomFull,
+                "NOT_ALLOWED" => Self::Unauthorized,
+                _ => Self::ConnectFailed,
+            },
+            _ => Self::ConnectFailed,
+        }
+    }
+
+    pub fn from_disconnect_reason(reason: DisconnectReason) -> Self {
+        match reason {
+            DisconnectReason::ClientInitiated => Self::None,
+            DisconnectReason::DuplicateIdentity => Self::DuplicateIdentity,
+            DisconnectReason::ServerShutdown => Self::ServerShutdown,
+            DisconnectReason::ParticipantRemoved => Self::Kicked,
+            DisconnectReason::RoomDeleted | DisconnectReason::RoomClosed => Self::RoomClosed,
+            DisconnectReason::JoinFailure => Self::ConnectFailed,
+            DisconnectReason::SignalClose
+            | DisconnectReason::StateMismatch
+            | DisconnectReason::Migration => Self::ConnectionLost,
+            _ => Self::Unknown,
+        }
+    }
+}
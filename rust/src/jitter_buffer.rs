@@ -0,0 +1,290 @@
+use ringbuf::traits::{Consumer, Observer};
+use ringbuf::HeapCons;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::remote_audio::StereoFrame;
+
+/// Target depth the buffer starts at and never goes below.
+const MIN_TARGET_MS: usize = 20;
+/// How much the target grows on every underrun and shrinks after a stable stretch.
+const TARGET_STEP_MS: usize = 10;
+/// Time without underruns before the target is lowered again.
+const STABLE_PERIOD_MS: usize = 5000;
+/// Length of the block repeated during packet-loss concealment.
+const PLC_BLOCK_MS: usize = 10;
+/// Concealment fades to silence over this long.
+const PLC_FADE_MS: usize = 30;
+/// Real audio ramps back in over this long after concealment.
+const FADE_IN_MS: usize = 2;
+
+/// Counters published by a participant's jitter buffer, readable from any thread.
+#[derive(Default)]
+pub struct AudioStats {
+    depth_frames: AtomicUsize,
+    target_frames: AtomicUsize,
+    underruns: AtomicU64,
+    overruns: AtomicU64,
+    concealed_frames: AtomicU64,
+    dropped_frames: AtomicU64,
+    frames_per_ms: AtomicUsize,
+}
+
+/// Point-in-time copy of `AudioStats`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AudioStatsSnapshot {
+    pub depth_ms: f64,
+    pub target_ms: f64,
+    pub underruns: u64,
+    pub overruns: u64,
+    pub concealed_ms: f64,
+    pub dropped_ms: f64,
+}
+
+impl AudioStats {
+    /// Records frames the producer had to drop because the buffer was full.
+    pub fn record_dropped(&self, frames: usize) {
+        if frames > 0 {
+            self.overruns.fetch_add(1, Ordering::Relaxed);
+            self.dropped_frames.fetch_add(frames as u64, Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> AudioStatsSnapshot {
+        let frames_per_ms = self.frames_per_ms.load(Ordering::Relaxed).max(1) as f64;
+        let to_ms = |frames: f64| frames / frames_per_ms;
+        AudioStatsSnapshot {
+            depth_ms: to_ms(self.depth_frames.load(Ordering::Relaxed) as f64),
+            target_ms: to_ms(self.target_frames.load(Ordering::Relaxed) as f64),
+            underruns: self.underruns.load(Ordering::Relaxed),
+            overruns: self.overruns.load(Ordering::Relaxed),
+            concealed_ms: to_ms(self.concealed_frames.load(Ordering::Relaxed) as f64),
+            dropped_ms: to_ms(self.dropped_frames.load(Ordering::Relaxed) as f64),
+        }
+    }
+}
+
+/// Smooths bursty network delivery for one participant.
+///
+/// Holds back playback until `target` frames are queued, conceals underruns
+/// by repeating the last block with a fade, and drops the oldest audio when
+/// the queue grows well past the target. The target grows on underruns and
+/// shrinks again after a stretch without any.
+pub struct JitterBuffer {
+    consumer: HeapCons<StereoFrame>,
+    stats: Arc<AudioStats>,
+    frames_per_ms: usize,
+
+    target: usize,
+    max_target: usize,
+    buffering: bool,
+    has_played: bool,
+    stable_frames: usize,
+
+    history: Vec<StereoFrame>,
+    history_pos: usize,
+    plc_pos: usize,
+    plc_gain: f32,
+    plc_decay: f32,
+    fade_in: usize,
+}
+
+impl JitterBuffer {
+    pub fn new(consumer: HeapCons<StereoFrame>, stats: Arc<AudioStats>, sample_rate: u32) -> Self {
+        let frames_per_ms = (sample_rate / 1000).max(1) as usize;
+        // Leave room above the largest target for overrun detection
+        let max_target = (consumer.capacity().get() / 2).max(MIN_TARGET_MS * frames_per_ms);
+        let plc_frames = (PLC_FADE_MS * frames_per_ms) as f32;
+
+        let buffer = Self {
+            consumer,
+            stats,
+            frames_per_ms,
+            target: MIN_TARGET_MS * frames_per_ms,
+            max_target,
+            buffering: true,
+            has_played: false,
+            stable_frames: 0,
+            history: vec![[0.0; 2]; PLC_BLOCK_MS * frames_per_ms],
+            history_pos: 0,
+            plc_pos: 0,
+            plc_gain: 0.0,
+            // Reach -60 dB by the end of the fade
+            plc_decay: 0.001f32.powf(1.0 / plc_frames),
+            fade_in: 0,
+        };
+        buffer.stats.frames_per_ms.store(frames_per_ms, Ordering::Relaxed);
+        buffer.stats.target_frames.store(buffer.target, Ordering::Relaxed);
+        buffer
+    }
+
+    /// Produces exactly `count` frames, real or concealed, passing each to `write`.
+    pub fn read(&mut self, count: usize, mut write: impl FnMut(StereoFrame)) {
+        self.trim_overrun();
+
+        if self.buffering && self.consumer.occupied_len() >= self.target {
+            self.buffering = false;
+            self.fade_in = FADE_IN_MS * self.frames_per_ms;
+        }
+
+        for _ in 0..count {
+            let frame = if self.buffering {
+                None
+            } else {
+                self.consumer.try_pop()
+            };
+
+            match frame {
+                Some(frame) => write(self.play(frame)),
+                None => {
+                    if !self.buffering {
+                        self.on_underrun();
+                    }
+                    write(self.conceal());
+                }
+            }
+        }
+
+        self.adapt();
+        self.stats.depth_frames.store(self.consumer.occupied_len(), Ordering::Relaxed);
+    }
+
+    fn play(&mut self, frame: StereoFrame) -> StereoFrame {
+        self.history[self.history_pos] = frame;
+        self.history_pos = (self.history_pos + 1) % self.history.len();
+        self.plc_pos = 0;
+        self.plc_gain = 1.0;
+        self.has_played = true;
+        self.stable_frames += 1;
+
+        if self.fade_in > 0 {
+            let ramp = FADE_IN_MS * self.frames_per_ms;
+            let gain = 1.0 - self.fade_in as f32 / ramp as f32;
+            self.fade_in -= 1;
+            return [frame[0] * gain, frame[1] * gain];
+        }
+        frame
+    }
+
+    fn conceal(&mut self) -> StereoFrame {
+        // Waiting for the first audio isn't a gap in the stream
+        if self.has_played {
+            self.stats.concealed_frames.fetch_add(1, Ordering::Relaxed);
+        }
+        if self.plc_gain <= 0.001 {
+            return [0.0; 2];
+        }
+
+        // Replay the last block starting from its oldest frame
+        let index = (self.history_pos + self.plc_pos) % self.history.len();
+        let [left, right] = self.history[index];
+        self.plc_pos += 1;
+        let gain = self.plc_gain;
+        self.plc_gain *= self.plc_decay;
+        [left * gain, right * gain]
+    }
+
+    fn on_underrun(&mut self) {
+        self.buffering = true;
+        self.stable_frames = 0;
+        self.target = (self.target + TARGET_STEP_MS * self.frames_per_ms).min(self.max_target);
+        self.stats.underruns.fetch_add(1, Ordering::Relaxed);
+        self.stats.target_frames.store(self.target, Ordering::Relaxed);
+    }
+
+    /// Drops the oldest audio when the queue runs far ahead of the target.
+    fn trim_overrun(&mut self) {
+        let queued = self.consumer.occupied_len();
+        let high_water = self.target * 2 + TARGET_STEP_MS * self.frames_per_ms;
+        if queued > high_water {
+            let dropped = self.consumer.skip(queued - self.target);
+            self.stats.record_dropped(dropped);
+        }
+    }
+
+    fn adapt(&mut self) {
+        let min_target = MIN_TARGET_MS * self.frames_per_ms;
+        if self.stable_frames >= STABLE_PERIOD_MS * self.frames_per_ms && self.target > min_target {
+            self.target = (self.target - TARGET_STEP_MS * self.frames_per_ms).max(min_target);
+            self.stable_frames = 0;
+            self.stats.target_frames.store(self.target, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ringbuf::traits::{Producer, Split};
+    use ringbuf::{HeapProd, HeapRb};
+
+    const RATE: u32 = 48000;
+    const FRAMES_PER_MS: usize = 48;
+
+    fn buffer() -> (HeapProd<StereoFrame>, JitterBuffer, Arc<AudioStats>) {
+        let (producer, consumer) = HeapRb::<StereoFrame>::new(RATE as usize).split();
+        let stats = Arc::new(AudioStats::default());
+        (producer, JitterBuffer::new(consumer, stats.clone(), RATE), stats)
+    }
+
+    fn push_ms(producer: &mut HeapProd<StereoFrame>, ms: usize) {
+        let frames = vec![[0.5; 2]; ms * FRAMES_PER_MS];
+        assert_eq!(producer.push_slice(&frames), frames.len());
+    }
+
+    fn read_ms(buffer: &mut JitterBuffer, ms: usize) {
+        buffer.read(ms * FRAMES_PER_MS, |_| {});
+    }
+
+    #[test]
+    fn underrun_grows_target() {
+        let (mut producer, mut buffer, stats) = buffer();
+        push_ms(&mut producer, MIN_TARGET_MS);
+        read_ms(&mut buffer, MIN_TARGET_MS + 10);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.underruns, 1);
+        assert_eq!(snapshot.target_ms, (MIN_TARGET_MS + TARGET_STEP_MS) as f64);
+        assert_eq!(snapshot.concealed_ms, 10.0);
+    }
+
+    #[test]
+    fn waiting_for_first_audio_is_not_concealment() {
+        let (_producer, mut buffer, stats) = buffer();
+        read_ms(&mut buffer, 100);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.underruns, 0);
+        assert_eq!(snapshot.concealed_ms, 0.0);
+    }
+
+    #[test]
+    fn target_shrinks_after_stable_period() {
+        let (mut producer, mut buffer, stats) = buffer();
+        push_ms(&mut producer, MIN_TARGET_MS);
+        read_ms(&mut buffer, MIN_TARGET_MS + 10);
+        assert_eq!(stats.snapshot().target_ms, (MIN_TARGET_MS + TARGET_STEP_MS) as f64);
+
+        push_ms(&mut producer, MIN_TARGET_MS + TARGET_STEP_MS);
+        for _ in 0..STABLE_PERIOD_MS / 10 {
+            push_ms(&mut producer, 10);
+            read_ms(&mut buffer, 10);
+        }
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.underruns, 1);
+        assert_eq!(snapshot.target_ms, MIN_TARGET_MS as f64);
+    }
+
+    #[test]
+    fn overrun_trims_to_target() {
+        let (mut producer, mut buffer, stats) = buffer();
+        push_ms(&mut producer, 200);
+        read_ms(&mut buffer, 0);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.overruns, 1);
+        assert_eq!(snapshot.dropped_ms, (200 - MIN_TARGET_MS) as f64);
+        assert_eq!(snapshot.depth_ms, MIN_TARGET_MS as f64);
+    }
+}
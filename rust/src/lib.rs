@@ -1,8 +1,28 @@
 use godot::prelude::*;
 
 
+mod access_token;
 mod audio_handler;
+mod audio_level;
+mod audio_options;
+mod audio_stream;
+mod connection_state;
+mod error_code;
+mod jitter_buffer;
 mod livekit_client;
+mod local_audio;
+mod local_tracks;
+mod local_video;
+mod participant_audio;
+mod proximity_voice;
+mod reconnect;
+mod remote_audio;
+mod remote_video;
+mod resampler;
+mod video_options;
+mod video_source;
+mod video_texture;
+mod voice_activity;
 
 struct LiveKitExtension;
 
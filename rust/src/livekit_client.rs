@@ -1,28 +1,108 @@
 use godot::prelude::*;
+use godot::classes::{
+    AudioServer, AudioStreamGenerator, AudioStreamGeneratorPlayback, AudioStreamMicrophone,
+    AudioStreamPlayer, Input, InputMap, ProjectSettings, Viewport,
+};
 use livekit::{
-    options::TrackPublishOptions,
+    id::TrackSid,
+    participant::{ConnectionQuality, RemoteParticipant},
+    track::{LocalAudioTrack, RemoteTrack, TrackKind, TrackSource},
     webrtc::{
-        audio_frame::AudioFrame,
-        audio_source::native::NativeAudioSource,
-        prelude::{AudioSourceOptions, RtcAudioSource},
+        audio_source::native::NativeAudioSource, audio_stream::native::NativeAudioStream,
+        video_stream::native::NativeVideoStream,
     },
-    Room, RoomEvent, RoomOptions,
+    DataPacket, Room, RoomError, RoomEvent, RoomOptions,
 };
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use futures_util::stream::StreamExt;
 use tokio::runtime::Runtime;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::audio_handler::AudioHandler;
+use crate::audio_level::{measure, AudioLevel, AudioLevels};
+use crate::audio_options::{LiveKitAudioOptions, AudioTrackOptions};
+use crate::audio_stream::LiveKitAudioStream;
+use crate::connection_state::{ConnectionState, SharedConnectionState};
+use crate::error_code::ErrorCode;
+use crate::local_audio::{capture_chunk, feed_source, publish_source, FrameChunker};
+use crate::local_tracks::{LocalSource, LocalTracks};
+use crate::local_video::{feed_video_source, VideoInput};
+use crate::participant_audio::ParticipantAudio;
+use crate::remote_audio::{
+    audio_channel, RemoteAudioReceiver, RemoteAudioSink, RemoteAudioSinks, REMOTE_CHANNELS,
+    REMOTE_SAMPLE_RATE,
+};
+use crate::reconnect::ReconnectPolicy;
+use crate::remote_video::{
+    RemoteVideoSinks, RgbaFrame, VideoPreference, VideoPreferences, VideoQuality,
+};
+use crate::video_options::LiveKitVideoOptions;
+use crate::video_source::{image_to_rgba, LiveKitVideoSource};
+use crate::resampler::ResamplerQuality;
+use crate::video_texture::LiveKitVideoTexture;
+use crate::voice_activity::{SharedMicGate, VoiceActivationMode, VoiceActivityDetector};
 
 #[derive(Clone, Debug)]
 enum InternalEvent {
     RoomConnected,
-    RoomDisconnected,
+    RoomDisconnected(ErrorCode),
     ParticipantJoined(String),
     ParticipantLeft(String),
     AudioFrame(String, Vec<Vector2>),
     ChatMessage(String, String, u64), // sender_identity, message, timestamp
+    DataReceived(String, String, Arc<Vec<u8>>), // sender_identity, topic, payload
     ParticipantMetadataChanged(String, String), // identity, username
-    Error(String),
+    ParticipantMuted(String, bool), // identity, muted
+    ActiveSpeakersChanged(Vec<String>), // loudest first
+    ConnectionQualityChanged(String, ConnectionQuality), // identity, quality
+    VideoTrackSubscribed(String, String), // identity, track name
+    VideoTrackUnsubscribed(String, String), // identity, track name
+    LocalSpeakingChanged(bool),
+    Reconnecting(u32), // attempt, 0 while the SDK resumes the session itself
+    Reconnected,
+    StateChanged(ConnectionState, ConnectionState), // old, new
+    Error(ErrorCode, String),
+}
+
+/// Extra audio pushed to attached generators each tick on top of the elapsed
+/// time, so they fill up to their buffer length instead of running on empty.
+const GENERATOR_SLACK_MS: f64 = 10.0;
+
+/// A player attached with `attach_participant_player`, filled from `process`.
+struct AttachedPlayer {
+    player: Gd<Node>,
+    _sink: Arc<RemoteAudioSink>,
+    receiver: RemoteAudioReceiver,
+    sample_rate: u32, // The generator's mix rate
+}
+
+impl AttachedPlayer {
+    /// The generator playback to fill, if the player is playing. Every
+    /// `play()` creates a new one, so it is looked up each tick.
+    fn playback(&self) -> Option<Gd<AudioStreamGeneratorPlayback>> {
+        let mut player = self.player.clone();
+        if !player.call("is_playing", &[]).booleanize() {
+            return None;
+        }
+        player.call("get_stream_playback", &[]).try_to().ok()
+    }
+}
+
+/// A bus published with `publish_audio_bus`, captured from `process`.
+struct BusCapture {
+    capture: AudioHandler,
+    sender: mpsc::UnboundedSender<Vec<f32>>, // Dropping it ends the feeder task
+}
+
+/// A viewport published with `publish_viewport`, read back from `process`.
+struct ViewportCapture {
+    viewport: Gd<Viewport>,
+    interval: f64, // Seconds between frames
+    elapsed: f64,
+    sender: mpsc::UnboundedSender<VideoInput>, // Dropping it ends the feeder task
 }
 
 #[derive(GodotClass)]
@@ -33,11 +113,68 @@ pub struct LiveKitManager {
     // State
     runtime: Option<Runtime>,
     event_receiver: Option<mpsc::UnboundedReceiver<InternalEvent>>,
-    audio_sender: Option<mpsc::UnboundedSender<Vec<f32>>>,
+    audio_sender: Option<mpsc::UnboundedSender<(u32, Vec<f32>)>>, // (sample rate, samples)
     room: Arc<Mutex<Option<Arc<Room>>>>, // Store room for sending messages
-    is_connected: Arc<Mutex<bool>>,
+    connection_state: SharedConnectionState,
+    audio_sinks: RemoteAudioSinks, // Native playback targets per participant
+    attached_players: HashMap<String, AttachedPlayer>,
+    video_sinks: RemoteVideoSinks, // Texture slots filled by the video decoding tasks
+    video_textures: Vec<Gd<LiveKitVideoTexture>>, // Updated from process while referenced elsewhere
+    video_preferences: VideoPreferences, // set_video_quality / set_video_dimensions requests
+    emit_audio_frames: Arc<AtomicBool>, // on_audio_frame is opt-in, for visualisation
     mic_sample_rate: i32,
+    mic_capture: Option<AudioHandler>, // Set by start_microphone
+    mic_player: Option<Gd<AudioStreamPlayer>>,
+    mic_options: AudioTrackOptions, // Applied when the mic source is created
+    mic_source: Arc<Mutex<Option<MicSource>>>, // Set while a session runs; the feeder writes to it
+    mic_track: Arc<Mutex<Option<LocalAudioTrack>>>, // Published by the session task
+    mic_muted: Arc<AtomicBool>, // Survives reconnects, applied to every republished track
+    mic_gate: SharedMicGate, // Voice activation settings read by the feeder task
+    local_level: Arc<AudioLevel>, // Measured by the feeder task before gating
+    participant_levels: AudioLevels, // Measured by each decoding task
+    levels_elapsed: f64,
+    subscriptions: Arc<Mutex<HashMap<String, bool>>>, // set_participant_subscribed overrides
+    active_speakers: Vec<String>, // From the last ActiveSpeakersChanged, loudest first
+    bus_captures: HashMap<String, BusCapture>, // Keyed by track name
+    viewport_captures: HashMap<String, ViewportCapture>, // Keyed by track name
+    video_sources: HashMap<String, Gd<LiveKitVideoSource>>, // Keyed by track name
+    local_tracks: LocalTracks, // Bus and viewport tracks, republished on every (re)connect
     disconnect_tx: Option<tokio::sync::oneshot::Sender<()>>,
+
+    // Reconnect policy, read when connect_to_room is called
+    #[var]
+    auto_reconnect: bool,
+    #[var]
+    reconnect_max_attempts: i32,
+    #[var]
+    reconnect_base_delay_ms: i32,
+    #[var]
+    reconnect_max_delay_ms: i32,
+
+    // Subscribe to every published track on connect; read when connect_to_room is called
+    #[var]
+    auto_subscribe: bool,
+
+    // Let the server adapt video to the size requested with set_video_quality /
+    // set_video_dimensions; read when connect_to_room is called
+    #[var]
+    adaptive_stream: bool,
+
+    // InputMap action held to talk in VOICE_PUSH_TO_TALK mode
+    #[var]
+    push_to_talk_action: GString,
+    // Keeps the mic open this long after the action is released
+    #[var]
+    push_to_talk_release_ms: i32,
+    talk_tail_remaining: f64,
+
+    // audio_levels_updated is emitted this often; 0 disables it
+    #[var]
+    audio_levels_interval_ms: i32,
+
+    // One of the RESAMPLER_* constants, used for the mic and for players created afterwards
+    #[var]
+    resampler_quality: i32,
 }
 
 #[godot_api]
@@ -49,9 +186,42 @@ impl INode for LiveKitManager {
             event_receiver: None,
             audio_sender: None,
             room: Arc::new(Mutex::new(None)),
-            is_connected: Arc::new(Mutex::new(false)),
+            connection_state: SharedConnectionState::new(),
+            audio_sinks: RemoteAudioSinks::default(),
+            attached_players: HashMap::new(),
+            video_sinks: RemoteVideoSinks::default(),
+            video_textures: Vec::new(),
+            video_preferences: VideoPreferences::default(),
+            emit_audio_frames: Arc::new(AtomicBool::new(false)),
             mic_sample_rate: 48000, // Default
+            mic_capture: None,
+            mic_player: None,
+            mic_options: AudioTrackOptions::default(),
+            mic_source: Arc::new(Mutex::new(None)),
+            mic_track: Arc::new(Mutex::new(None)),
+            mic_muted: Arc::new(AtomicBool::new(false)),
+            mic_gate: SharedMicGate::default(),
+            local_level: Arc::new(AudioLevel::default()),
+            participant_levels: AudioLevels::default(),
+            levels_elapsed: 0.0,
+            subscriptions: Arc::new(Mutex::new(HashMap::new())),
+            active_speakers: Vec::new(),
+            bus_captures: HashMap::new(),
+            viewport_captures: HashMap::new(),
+            video_sources: HashMap::new(),
+            local_tracks: LocalTracks::default(),
             disconnect_tx: None,
+            auto_reconnect: ReconnectPolicy::default().enabled,
+            reconnect_max_attempts: ReconnectPolicy::default().max_attempts as i32,
+            reconnect_base_delay_ms: ReconnectPolicy::default().base_delay_ms as i32,
+            reconnect_max_delay_ms: ReconnectPolicy::default().max_delay_ms as i32,
+            auto_subscribe: true,
+            adaptive_stream: false,
+            push_to_talk_action: GString::from("push_to_talk"),
+            push_to_talk_release_ms: 200,
+            talk_tail_remaining: 0.0,
+            audio_levels_interval_ms: 0,
+            resampler_quality: ResamplerQuality::Cubic as i32,
         }
     }
 
@@ -75,7 +245,65 @@ impl INode for LiveKitManager {
         log::info!("LiveKitManager::ready: Relying on lazy WebRTC init (JNI_OnLoad skipped crash fix)");
     }
 
-    fn process(&mut self, _delta: f64) {
+    fn process(&mut self, delta: f64) {
+        // Top up attached players with the audio played since the last frame.
+        // Players freed without detach_participant_player are dropped with their sinks
+        self.attached_players.retain(|_, attached| attached.player.is_instance_valid());
+        for attached in self.attached_players.values_mut() {
+            let Some(mut playback) = attached.playback() else {
+                continue;
+            };
+            let seconds = delta + GENERATOR_SLACK_MS / 1000.0;
+            let max_frames = (seconds * attached.sample_rate as f64) as usize;
+            attached.receiver.drain_into(&mut playback, max_frames);
+        }
+
+        // Upload the newest video frames; textures nobody else holds are released
+        self.video_textures.retain(|texture| texture.get_reference_count() > 1);
+        for texture in &mut self.video_textures {
+            texture.bind_mut().update_frame();
+        }
+
+        self.update_push_to_talk(delta);
+        self.update_audio_levels(delta);
+
+        // Forward everything the mic captured since the last frame
+        if let Some(capture) = &mut self.mic_capture {
+            if let Some(samples) = capture.capture_microphone_audio() {
+                if let Some(sender) = &self.audio_sender {
+                    sender.send((self.mic_sample_rate as u32, samples)).ok();
+                }
+            }
+        }
+
+        // Same for published buses, which keep being fed between sessions
+        for bus in self.bus_captures.values_mut() {
+            if let Some(samples) = bus.capture.capture_microphone_audio() {
+                bus.sender.send(samples).ok();
+            }
+        }
+
+        // Read back published viewports at their frame rate
+        let mut freed = Vec::new();
+        for (name, capture) in self.viewport_captures.iter_mut() {
+            if !capture.viewport.is_instance_valid() {
+                freed.push(name.clone());
+                continue;
+            }
+            capture.elapsed += delta;
+            if capture.elapsed < capture.interval {
+                continue;
+            }
+            // Don't try to catch up after a hitch
+            capture.elapsed = (capture.elapsed - capture.interval).min(capture.interval);
+            if let Some(frame) = read_viewport(&capture.viewport) {
+                capture.sender.send(VideoInput { frame, timestamp_us: None }).ok();
+            }
+        }
+        for name in freed {
+            self.unpublish_track(GString::from(name));
+        }
+
         // Process events from the async task
         let mut events = Vec::new();
         if let Some(receiver) = &mut self.event_receiver {
@@ -89,8 +317,10 @@ impl INode for LiveKitManager {
                 InternalEvent::RoomConnected => {
                     self.base_mut().emit_signal("room_connected", &[]);
                 }
-                InternalEvent::RoomDisconnected => {
-                    self.base_mut().emit_signal("room_disconnected", &[]);
+                InternalEvent::RoomDisconnected(reason) => {
+                    self.active_speakers.clear();
+                    self.base_mut()
+                        .emit_signal("room_disconnected", &[(reason as i64).to_variant()]);
                 }
                 InternalEvent::ParticipantJoined(id) => {
                     self.base_mut()
@@ -114,16 +344,69 @@ impl INode for LiveKitManager {
                         &[sender.to_variant(), message.to_variant(), (timestamp as i64).to_variant()],
                     );
                 }
+                InternalEvent::DataReceived(sender, topic, payload) => {
+                    let packed = PackedByteArray::from(payload.as_slice());
+                    self.base_mut().emit_signal(
+                        "data_received",
+                        &[sender.to_variant(), topic.to_variant(), packed.to_variant()],
+                    );
+                }
                 InternalEvent::ParticipantMetadataChanged(identity, username) => {
                     self.base_mut().emit_signal(
                         "participant_name_changed",
                         &[identity.to_variant(), username.to_variant()],
                     );
                 }
-                InternalEvent::Error(msg) => {
-                    godot_error!("LiveKit Error: {}", msg);
+                InternalEvent::ParticipantMuted(identity, muted) => {
+                    self.base_mut().emit_signal(
+                        "participant_muted",
+                        &[identity.to_variant(), muted.to_variant()],
+                    );
+                }
+                InternalEvent::ActiveSpeakersChanged(speakers) => {
+                    self.active_speakers = speakers;
+                    let identities = self.get_active_speakers();
+                    self.base_mut()
+                        .emit_signal("active_speakers_changed", &[identities.to_variant()]);
+                }
+                InternalEvent::ConnectionQualityChanged(identity, quality) => {
+                    self.base_mut().emit_signal(
+                        "connection_quality_changed",
+                        &[identity.to_variant(), quality_code(quality).to_variant()],
+                    );
+                }
+                InternalEvent::VideoTrackSubscribed(identity, track_name) => {
+                    self.base_mut().emit_signal(
+                        "video_track_subscribed",
+                        &[identity.to_variant(), track_name.to_variant()],
+                    );
+                }
+                InternalEvent::VideoTrackUnsubscribed(identity, track_name) => {
+                    self.base_mut().emit_signal(
+                        "video_track_unsubscribed",
+                        &[identity.to_variant(), track_name.to_variant()],
+                    );
+                }
+                InternalEvent::LocalSpeakingChanged(speaking) => {
+                    self.base_mut()
+                        .emit_signal("local_speaking_changed", &[speaking.to_variant()]);
+                }
+                InternalEvent::Reconnecting(attempt) => {
                     self.base_mut()
-                        .emit_signal("error_occurred", &[msg.to_variant()]);
+                        .emit_signal("reconnecting", &[(attempt as i64).to_variant()]);
+                }
+                InternalEvent::Reconnected => {
+                    self.base_mut().emit_signal("reconnected", &[]);
+                }
+                InternalEvent::StateChanged(old, new) => {
+                    self.emit_state_changed(old, new);
+                }
+                InternalEvent::Error(code, msg) => {
+                    godot_error!("LiveKit Error ({:?}): {}", code, msg);
+                    self.base_mut().emit_signal(
+                        "error_occurred",
+                        &[(code as i64).to_variant(), msg.to_variant()],
+                    );
                 }
             }
         }
@@ -135,51 +418,321 @@ impl LiveKitManager {
     #[signal]
     fn room_connected();
     #[signal]
-    fn room_disconnected();
+    fn room_disconnected(reason: i64);
     #[signal]
     fn participant_joined(identity: GString);
     #[signal]
     fn participant_left(identity: GString);
     #[signal]
-    fn error_occurred(message: GString);
+    fn error_occurred(code: i64, message: GString);
     #[signal]
     fn on_audio_frame(peer_id: GString, frame: PackedVector2Array);
     #[signal]
     fn chat_message_received(sender: GString, message: GString, timestamp: i64);
     #[signal]
+    fn data_received(sender: GString, topic: GString, payload: PackedByteArray);
+    #[signal]
     fn participant_name_changed(identity: GString, username: GString);
+    #[signal]
+    fn connection_state_changed(old_state: i64, new_state: i64);
+    #[signal]
+    fn reconnecting(attempt: i64);
+    #[signal]
+    fn reconnected();
+    #[signal]
+    fn local_mute_changed(muted: bool);
+    #[signal]
+    fn local_speaking_changed(speaking: bool);
+    #[signal]
+    fn audio_levels_updated(levels: Dictionary);
+    #[signal]
+    fn participant_muted(identity: GString, muted: bool);
+    #[signal]
+    fn active_speakers_changed(identities: PackedStringArray);
+    #[signal]
+    fn connection_quality_changed(identity: GString, quality: i64);
+    #[signal]
+    fn video_track_subscribed(identity: GString, track_name: GString);
+    #[signal]
+    fn video_track_unsubscribed(identity: GString, track_name: GString);
 
+    /// Sets the rate of samples passed to `push_mic_audio`. They are resampled
+    /// to 48 kHz before publishing; a running session follows changes.
+    /// `start_microphone` sets this itself.
     #[func]
     pub fn set_mic_sample_rate(&mut self, rate: i32) {
         self.mic_sample_rate = rate;
         godot_print!("LiveKit: Mic sample rate set to {}", rate);
     }
 
+    #[constant]
+    const RESAMPLER_LINEAR: i32 = ResamplerQuality::Linear as i32;
+    #[constant]
+    const RESAMPLER_CUBIC: i32 = ResamplerQuality::Cubic as i32;
+    #[constant]
+    const RESAMPLER_SINC: i32 = ResamplerQuality::Sinc as i32;
+
+    #[constant]
+    const VOICE_OPEN_MIC: i32 = VoiceActivationMode::OpenMic as i32;
+    #[constant]
+    const VOICE_ACTIVITY: i32 = VoiceActivationMode::VoiceActivity as i32;
+    #[constant]
+    const VOICE_PUSH_TO_TALK: i32 = VoiceActivationMode::PushToTalk as i32;
+
+    #[constant]
+    const QUALITY_EXCELLENT: i32 = quality_code(ConnectionQuality::Excellent);
+    #[constant]
+    const QUALITY_GOOD: i32 = quality_code(ConnectionQuality::Good);
+    #[constant]
+    const QUALITY_POOR: i32 = quality_code(ConnectionQuality::Poor);
+    #[constant]
+    const QUALITY_LOST: i32 = quality_code(ConnectionQuality::Lost);
+
+    #[constant]
+    const VIDEO_QUALITY_LOW: i32 = VideoQuality::Low as i32;
+    #[constant]
+    const VIDEO_QUALITY_MEDIUM: i32 = VideoQuality::Medium as i32;
+    #[constant]
+    const VIDEO_QUALITY_HIGH: i32 = VideoQuality::High as i32;
+
+    #[constant]
+    const STATE_DISCONNECTED: i32 = ConnectionState::Disconnected as i32;
+    #[constant]
+    const STATE_CONNECTING: i32 = ConnectionState::Connecting as i32;
+    #[constant]
+    const STATE_CONNECTED: i32 = ConnectionState::Connected as i32;
+    #[constant]
+    const STATE_RECONNECTING: i32 = ConnectionState::Reconnecting as i32;
+    #[constant]
+    const STATE_DISCONNECTING: i32 = ConnectionState::Disconnecting as i32;
+    #[constant]
+    const STATE_FAILED: i32 = ConnectionState::Failed as i32;
+
+    #[constant]
+    const ERROR_NONE: i32 = ErrorCode::None as i32;
+    #[constant]
+    const ERROR_UNKNOWN: i32 = ErrorCode::Unknown as i32;
+    #[constant]
+    const ERROR_CONNECT_FAILED: i32 = ErrorCode::ConnectFailed as i32;
+    #[constant]
+    const ERROR_TOKEN_EXPIRED: i32 = ErrorCode::TokenExpired as i32;
+    #[constant]
+    const ERROR_UNAUTHORIZED: i32 = ErrorCode::Unauthorized as i32;
+    #[constant]
+    const ERROR_ROOM_FULL: i32 = ErrorCode::RoomFull as i32;
+    #[constant]
+    const ERROR_KICKED: i32 = ErrorCode::Kicked as i32;
+    #[constant]
+    const ERROR_DUPLICATE_IDENTITY: i32 = ErrorCode::DuplicateIdentity as i32;
+    #[constant]
+    const ERROR_SIGNAL_TIMEOUT: i32 = ErrorCode::SignalTimeout as i32;
+    #[constant]
+    const ERROR_CONNECTION_LOST: i32 = ErrorCode::ConnectionLost as i32;
+    #[constant]
+    const ERROR_SERVER_SHUTDOWN: i32 = ErrorCode::ServerShutdown as i32;
+    #[constant]
+    const ERROR_ROOM_CLOSED: i32 = ErrorCode::RoomClosed as i32;
+    #[constant]
+    const ERROR_PUBLISH_FAILED: i32 = ErrorCode::PublishFailed as i32;
+    #[constant]
+    const ERROR_RECONNECT_FAILED: i32 = ErrorCode::ReconnectFailed as i32;
+
+    fn emit_state_changed(&mut self, old: ConnectionState, new: ConnectionState) {
+        godot_print!("LiveKit: Connection state {:?} -> {:?}", old, new);
+        self.base_mut().emit_signal(
+            "connection_state_changed",
+            &[(old as i64).to_variant(), (new as i64).to_variant()],
+        );
+    }
+
+    /// Transitions the shared state from the main thread and emits the signal right away.
+    fn set_connection_state(&mut self, new: ConnectionState) {
+        if let Some(old) = self.connection_state.transition(new) {
+            self.emit_state_changed(old, new);
+        }
+    }
+
+    /// The `resampler_quality` setting, or cubic if it isn't a `RESAMPLER_*` value.
+    fn resampler_quality(&self) -> ResamplerQuality {
+        ResamplerQuality::from_i32(self.resampler_quality).unwrap_or_else(|| {
+            godot_warn!(
+                "LiveKit: Unknown resampler quality {}, using RESAMPLER_CUBIC",
+                self.resampler_quality
+            );
+            ResamplerQuality::Cubic
+        })
+    }
+
+    /// Opens the mic gate while the push-to-talk action is held and for
+    /// `push_to_talk_release_ms` afterwards, so word endings aren't clipped.
+    fn update_push_to_talk(&mut self, delta: f64) {
+        let settings = self.mic_gate.settings();
+        if settings.mode != VoiceActivationMode::PushToTalk {
+            return;
+        }
+
+        let action = StringName::from(&self.push_to_talk_action);
+        let pressed = InputMap::singleton().has_action(&action)
+            && Input::singleton().is_action_pressed(&action);
+        if pressed {
+            self.talk_tail_remaining = self.push_to_talk_release_ms.max(0) as f64 / 1000.0;
+        } else {
+            self.talk_tail_remaining = (self.talk_tail_remaining - delta).max(0.0);
+        }
+
+        let open = pressed || self.talk_tail_remaining > 0.0;
+        if open != settings.talk_key_open {
+            self.mic_gate.update(|settings| settings.talk_key_open = open);
+            self.sync_mic_track_mute();
+        }
+    }
+
+    /// Emits `audio_levels_updated` every `audio_levels_interval_ms`.
+    fn update_audio_levels(&mut self, delta: f64) {
+        if self.audio_levels_interval_ms <= 0 {
+            return;
+        }
+        self.levels_elapsed += delta;
+        if self.levels_elapsed * 1000.0 < self.audio_levels_interval_ms as f64 {
+            return;
+        }
+        self.levels_elapsed = 0.0;
+
+        let mut levels = Dictionary::new();
+        for (identity, rms, peak) in self.participant_levels.snapshot() {
+            levels.set(identity, level_dictionary(rms, peak));
+        }
+        levels.set(
+            self.get_local_identity(),
+            level_dictionary(self.local_level.rms(), self.local_level.peak()),
+        );
+        self.base_mut()
+            .emit_signal("audio_levels_updated", &[levels.to_variant()]);
+    }
+
+    /// Registers an extra track and publishes it right away if connected;
+    /// otherwise the session task publishes it once the room is joined.
+    fn add_local_track(&self, name: &str, source: LocalSource) {
+        self.local_tracks.insert(name, source);
+        let room = self.room.lock().unwrap().clone();
+        if let (Some(room), Some(runtime)) = (room, &self.runtime) {
+            for (name, source) in self.local_tracks.claim_unpublished() {
+                runtime.spawn(self.local_tracks.clone().publish(room.clone(), name, source));
+            }
+        }
+    }
+
+    fn set_video_preference(
+        &self,
+        identity: GString,
+        track_name: GString,
+        preference: VideoPreference,
+    ) {
+        let (identity, track_name) = (identity.to_string(), track_name.to_string());
+        self.video_preferences.set(&identity, &track_name, preference);
+
+        let room = self.room.lock().unwrap().clone();
+        if let (Some(room), Some(runtime)) = (room, &self.runtime) {
+            // The request is sent from a spawned task
+            let _guard = runtime.enter();
+            let participants = room.remote_participants();
+            let Some(participant) = participants
+                .values()
+                .find(|p| p.identity().as_str() == identity)
+            else {
+                return;
+            };
+            for publication in participant.track_publications().values() {
+                let matches = track_name.is_empty() || publication.name() == track_name;
+                if publication.kind() == TrackKind::Video && publication.is_subscribed() && matches {
+                    self.video_preferences.apply(&identity, publication);
+                }
+            }
+        }
+    }
+
+    /// Mutes or unmutes the published track to match the user mute and the
+    /// push-to-talk gate.
+    fn sync_mic_track_mute(&self) {
+        // Muting notifies the server from a spawned task, which needs the runtime
+        let Some(runtime) = &self.runtime else {
+            return;
+        };
+        let _guard = runtime.enter();
+
+        let mic_track = self.mic_track.lock().unwrap();
+        if let Some(track) = mic_track.as_ref() {
+            apply_track_mute(track, &self.mic_muted, &self.mic_gate);
+        }
+    }
+
+    fn reconnect_policy(&self) -> ReconnectPolicy {
+        ReconnectPolicy {
+            enabled: self.auto_reconnect,
+            max_attempts: self.reconnect_max_attempts.max(0) as u32,
+            base_delay_ms: self.reconnect_base_delay_ms.max(0) as u64,
+            max_delay_ms: self.reconnect_max_delay_ms.max(0) as u64,
+        }
+    }
+
     #[func]
     pub fn is_room_connected(&self) -> bool {
-        *self.is_connected.lock().unwrap()
+        self.connection_state.get() == ConnectionState::Connected
+    }
+
+    /// Whether connecting again can be expected to succeed after an `ERROR_*` code.
+    #[func]
+    pub fn is_error_retryable(&self, code: i32) -> bool {
+        ErrorCode::from_i32(code).is_some_and(ErrorCode::is_retryable)
+    }
+
+    /// Returns one of the `STATE_*` constants.
+    #[func]
+    pub fn get_connection_state(&self) -> i32 {
+        self.connection_state.get() as i32
     }
 
     #[func]
     pub fn disconnect_from_room(&mut self) {
         godot_print!("Disconnecting from room...");
-        
-        // Signal the async task to stop
-        if let Some(tx) = self.disconnect_tx.take() {
-            let _ = tx.send(());
-        }
-        
-        // Clear channels and room
+
+        // Stop feeding the mic and clear the room used for messages
         self.audio_sender = None;
-        self.event_receiver = None;
         *self.room.lock().unwrap() = None;
-        
-        *self.is_connected.lock().unwrap() = false;
+
+        // Signal the async task to stop. It closes the room and reports
+        // DISCONNECTED itself, so the event receiver stays alive until then.
+        match self.disconnect_tx.take() {
+            Some(tx) if !tx.is_closed() => {
+                // Set before signalling: a task that ends after this point
+                // finishes on DISCONNECTED instead of FAILED
+                self.set_connection_state(ConnectionState::Disconnecting);
+                if tx.send(()).is_err() {
+                    // The task finished in between
+                    self.set_connection_state(ConnectionState::Disconnected);
+                }
+            }
+            _ => {
+                // No live session task (never connected, failed or already gone)
+                self.set_connection_state(ConnectionState::Disconnected);
+            }
+        }
     }
 
     #[func]
     pub fn connect_to_room(&mut self, url: GString, token: GString) {
         godot_print!("LiveKit: connect_to_room called - URL: {}, Token length: {}", url, token.to_string().len());
+
+        let state = self.connection_state.get();
+        if state.is_active() {
+            godot_warn!("LiveKit: connect_to_room ignored, session already active ({:?})", state);
+            return;
+        }
+        if self.runtime.is_none() {
+            godot_error!("LiveKit: connect_to_room called before the node was ready");
+            return;
+        }
+        self.set_connection_state(ConnectionState::Connecting);
         
         let url = url.to_string();
         let token = token.to_string();
@@ -187,284 +740,1394 @@ impl LiveKitManager {
         let (event_tx, event_rx) = mpsc::unbounded_channel();
         self.event_receiver = Some(event_rx);
 
-        let (audio_tx, mut audio_rx) = mpsc::unbounded_channel::<Vec<f32>>();
+        let (audio_tx, audio_rx) = mpsc::unbounded_channel::<(u32, Vec<f32>)>();
         self.audio_sender = Some(audio_tx);
         
         // Create disconnect channel
         let (disconnect_tx, mut disconnect_rx) = tokio::sync::oneshot::channel();
         self.disconnect_tx = Some(disconnect_tx);
         
-        let is_connected = self.is_connected.clone();
+        let connection_state = self.connection_state.clone();
+        let shared = SessionShared {
+            connection_state: connection_state.clone(),
+            audio_sinks: self.audio_sinks.clone(),
+            video_sinks: self.video_sinks.clone(),
+            video_preferences: self.video_preferences.clone(),
+            emit_audio_frames: self.emit_audio_frames.clone(),
+            mic_source: self.mic_source.clone(),
+            mic_track: self.mic_track.clone(),
+            mic_muted: self.mic_muted.clone(),
+            subscriptions: self.subscriptions.clone(),
+            mic_gate: self.mic_gate.clone(),
+            participant_levels: self.participant_levels.clone(),
+            local_tracks: self.local_tracks.clone(),
+        };
         let room_storage = self.room.clone(); // Clone the Arc<Mutex> to store room later
         let mic_sample_rate = self.mic_sample_rate;
+        let quality = self.resampler_quality();
+        let mic_gate = self.mic_gate.clone();
+        let mic_options = self.mic_options;
+        let local_level = self.local_level.clone();
+        let policy = self.reconnect_policy();
+        let mut room_options = RoomOptions::default();
+        room_options.auto_subscribe = self.auto_subscribe;
+        room_options.adaptive_stream = self.adaptive_stream;
 
         if let Some(runtime) = &self.runtime {
             godot_print!("LiveKit: Runtime found, spawning connection task...");
             runtime.spawn(async move {
-                godot_print!("LiveKit: Connection task started - Connecting to {}", url);
-                let (room, mut room_events) = match Room::connect(&url, &token, RoomOptions::default()).await {
-                    Ok(res) => res,
-                    Err(e) => {
-                        event_tx
-                            .send(InternalEvent::Error(format!("Failed to connect: {}", e)))
-                            .ok();
-                        return;
-                    }
-                };
+                // The mic source outlives individual room connections so a reconnect
+                // only has to wrap it in a new track and publish it again.
+                // set_microphone_options swaps it for a new one.
+                *shared.mic_source.lock().unwrap() = Some(MicSource::new(mic_options));
+
+                // Spawn a task to feed audio data to the source
+                let chunker = FrameChunker::new(mic_sample_rate as u32, quality);
+                tokio::spawn(feed_microphone(
+                    shared.mic_source.clone(),
+                    audio_rx,
+                    chunker,
+                    mic_gate,
+                    local_level,
+                    event_tx.clone(),
+                ));
 
-                *is_connected.lock().unwrap() = true;
-                event_tx.send(InternalEvent::RoomConnected).ok();
+                let mut known_participants: HashSet<String> = HashSet::new();
+                let mut ever_connected = false;
+                let mut final_state = ConnectionState::Disconnected;
+                let mut disconnect_reason = ErrorCode::None;
+                let mut attempt: u32 = 0;
 
-                // Notify about participants already in the room
-                for participant in room.remote_participants().values() {
-                    event_tx
-                        .send(InternalEvent::ParticipantJoined(participant.identity().to_string()))
-                        .ok();
-                    
-                    // Check for existing metadata
-                    let metadata = participant.metadata();
-                    if !metadata.is_empty() {
-                        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&metadata) {
-                            if let Some(username) = json.get("username").and_then(|v| v.as_str()) {
-                                event_tx
-                                    .send(InternalEvent::ParticipantMetadataChanged(
-                                        participant.identity().to_string(),
-                                        username.to_string(),
-                                    ))
-                                    .ok();
+                loop {
+                    if attempt > 0 {
+                        if policy.attempts_exhausted(attempt) {
+                            event_tx
+                                .send(InternalEvent::Error(
+                                    ErrorCode::ReconnectFailed,
+                                    format!("Giving up after {} reconnect attempts", policy.max_attempts),
+                                ))
+                                .ok();
+                            final_state = ConnectionState::Failed;
+                            disconnect_reason = ErrorCode::ReconnectFailed;
+                            break;
+                        }
+
+                        let delay = policy.delay_for(attempt);
+                        godot_print!("LiveKit: Reconnect attempt {} in {} ms", attempt, delay.as_millis());
+                        set_state(&connection_state, &event_tx, ConnectionState::Reconnecting);
+                        event_tx.send(InternalEvent::Reconnecting(attempt)).ok();
+
+                        tokio::select! {
+                            _ = tokio::time::sleep(delay) => {}
+                            _ = &mut disconnect_rx => {
+                                // Requested by the user, not the earlier connection loss
+                                disconnect_reason = ErrorCode::None;
+                                break;
                             }
                         }
                     }
-                }
-
-                // Create a native audio source for the microphone
-                let source = NativeAudioSource::new(
-                    AudioSourceOptions {
-                        echo_cancellation: true,
-                        noise_suppression: true,
-                        auto_gain_control: true,
-                    },
-                    mic_sample_rate as u32,
-                    1, // Mono
-                    1000, // queue_size_ms - audio buffer queue size in milliseconds
-                );
-                
-                let track = livekit::track::LocalAudioTrack::create_audio_track(
-                    "mic",
-                    RtcAudioSource::Native(source.clone()),
-                );
-
-                if let Err(e) = room
-                    .local_participant()
-                    .publish_track(
-                        livekit::track::LocalTrack::Audio(track),
-                        TrackPublishOptions::default(),
-                    )
-                    .await
-                {
-                    event_tx
-                        .send(InternalEvent::Error(format!("Failed to publish mic: {}", e)))
-                        .ok();
-                }
-                
-                // Store the room reference for sending messages
-                let room_arc = Arc::new(room);
-                *room_storage.lock().unwrap() = Some(room_arc.clone());
 
-                // Spawn a task to feed audio data to the source
-                tokio::spawn(async move {
-                    let mut buffer: Vec<i16> = Vec::new();
-                    // 10ms at 48kHz = 480 samples
-                    let samples_per_10ms = (mic_sample_rate / 100) as usize; 
-
-                    while let Some(samples) = audio_rx.recv().await {
-                        // Convert f32 samples to i16 and append to buffer
-                        for sample in samples {
-                            let s = (sample.clamp(-1.0, 1.0) * 32767.0) as i16;
-                            buffer.push(s);
+                    godot_print!("LiveKit: Connection task started - Connecting to {}", url);
+                    let result = tokio::select! {
+                        res = Room::connect(&url, &token, room_options.clone()) => res,
+                        _ = &mut disconnect_rx => {
+                            disconnect_reason = ErrorCode::None;
+                            break;
                         }
+                    };
 
-                        // Process chunks of 10ms
-                        while buffer.len() >= samples_per_10ms {
-                            let chunk: Vec<i16> = buffer.drain(0..samples_per_10ms).collect();
-                            
-                            let frame = AudioFrame {
-                                data: std::borrow::Cow::Owned(chunk),
-                                sample_rate: mic_sample_rate as u32,
-                                num_channels: 1,
-                                samples_per_channel: samples_per_10ms as u32,
-                            };
-                            
-                            if let Err(e) = source.capture_frame(&frame).await {
-                                godot_error!("Failed to capture audio frame: {:?}", e);
+                    let (room, room_events) = match result {
+                        Ok(res) => res,
+                        Err(e) => {
+                            let code = ErrorCode::from_connect_error(&e);
+                            if attempt > 0 && code.is_retryable() {
+                                godot_warn!("LiveKit: Reconnect attempt {} failed: {}", attempt, e);
+                                attempt += 1;
+                                continue;
                             }
+
+                            event_tx
+                                .send(InternalEvent::Error(code, format!("Failed to connect: {}", e)))
+                                .ok();
+                            final_state = ConnectionState::Failed;
+                            disconnect_reason = code;
+                            break;
+                        }
+                    };
+
+                    // Publish a fresh track backed by the long-lived source
+                    let source = shared.mic_source.lock().unwrap().clone();
+                    let published = match source {
+                        Some(mic) => publish_microphone(&room, &mic).await,
+                        None => Err(RoomError::Internal("Microphone source missing".into())),
+                    };
+                    match published {
+                        Ok(track) => {
+                            // Under the lock so a concurrent mute change can't be missed
+                            let mut mic_track = shared.mic_track.lock().unwrap();
+                            apply_track_mute(&track, &shared.mic_muted, &shared.mic_gate);
+                            *mic_track = Some(track);
+                        }
+                        Err(e) => {
+                            event_tx
+                                .send(InternalEvent::Error(
+                                    ErrorCode::PublishFailed,
+                                    format!("Failed to publish mic: {}", e),
+                                ))
+                                .ok();
                         }
                     }
-                });
 
-                loop {
-                    tokio::select! {
-                        Some(event) = room_events.recv() => {
-                            match event {
-                                RoomEvent::ParticipantConnected(p) => {
-                                    event_tx
-                                        .send(InternalEvent::ParticipantJoined(p.identity().to_string()))
-                                        .ok();
-                                }
-                                RoomEvent::ParticipantDisconnected(p) => {
-                                    event_tx
-                                        .send(InternalEvent::ParticipantLeft(p.identity().to_string()))
-                                        .ok();
-                                }
-                                RoomEvent::TrackSubscribed {
-                                    track,
-                                    publication: _,
-                                    participant,
-                                } => {
-                                    if let livekit::track::RemoteTrack::Audio(audio_track) = track {
-                                        let event_tx_clone = event_tx.clone();
-                                        let participant_id = participant.identity().to_string();
-                                        let mut stream = livekit::webrtc::audio_stream::native::NativeAudioStream::new(
-                                            audio_track.rtc_track(),
-                                            48000, // sample rate
-                                            1,     // channels
-                                        );
-
-                                        tokio::spawn(async move {
-                                            while let Some(frame) = stream.next().await {
-                                                // frame is usually Vec<i16>
-                                                // Convert to Vector2 (stereo) for Godot
-                                                // Godot expects PackedVector2Array for stereo audio
-                                                let mut godot_frame = Vec::with_capacity(frame.data.len());
-                                                for &sample in frame.data.iter() {
-                                                    let f = (sample as f32) / 32768.0;
-                                                    godot_frame.push(Vector2::new(f, f));
-                                                }
-                                                
-                                                event_tx_clone
-                                                    .send(InternalEvent::AudioFrame(
-                                                        participant_id.clone(),
-                                                        godot_frame,
-                                                    ))
-                                                    .ok();
-                                            }
-                                         });
-                                    }
-                                }
-                                RoomEvent::ChatMessage { message, participant } => {
-                                    // Use built-in ChatMessage event
-                                    let sender_identity = participant
-                                        .map(|p| p.identity().to_string())
-                                        .unwrap_or_else(|| "Unknown".to_string());
-                                    
-                                    event_tx
-                                        .send(InternalEvent::ChatMessage(
-                                            sender_identity,
-                                            message.message,
-                                            message.timestamp as u64,
-                                        ))
-                                        .ok();
-                                }
-                                RoomEvent::ParticipantMetadataChanged { participant, old_metadata: _, metadata } => {
-                                    // Extract username from metadata
-                                    if !metadata.is_empty() {
-                                        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&metadata) {
-                                            if let Some(username) = json.get("username").and_then(|v| v.as_str()) {
-                                                event_tx
-                                                    .send(InternalEvent::ParticipantMetadataChanged(
-                                                        participant.identity().to_string(),
-                                                        username.to_string(),
-                                                    ))
-                                                    .ok();
-                                            }
-                                        }
-                                    }
-                                }
-                                _ => {}
+                    // Store the room reference for sending messages
+                    let room = Arc::new(room);
+                    *room_storage.lock().unwrap() = Some(room.clone());
+                    set_state(&connection_state, &event_tx, ConnectionState::Connected);
+
+                    // Claimed after the room is stored: a bus added meanwhile is
+                    // either seen here or published by publish_audio_bus itself
+                    for (name, source) in shared.local_tracks.claim_unpublished() {
+                        let local_tracks = shared.local_tracks.clone();
+                        tokio::spawn(local_tracks.publish(room.clone(), name, source));
+                    }
+
+                    if ever_connected {
+                        event_tx.send(InternalEvent::Reconnected).ok();
+                    } else {
+                        event_tx.send(InternalEvent::RoomConnected).ok();
+                    }
+                    ever_connected = true;
+
+                    sync_participants(&room, &mut known_participants, &event_tx);
+                    apply_subscriptions(&room, &shared.subscriptions);
+
+                    let exit = run_room_events(
+                        room_events,
+                        &mut disconnect_rx,
+                        &mut known_participants,
+                        &shared,
+                        &event_tx,
+                    )
+                    .await;
+
+                    *room_storage.lock().unwrap() = None;
+                    *shared.mic_track.lock().unwrap() = None;
+                    shared.local_tracks.clear_published();
+
+                    match exit {
+                        RoomExit::Requested => {
+                            disconnect_reason = ErrorCode::None;
+                            if let Err(e) = room.close().await {
+                                godot_warn!("LiveKit: Error while closing room: {}", e);
                             }
-                        }
-                        _ = &mut disconnect_rx => {
-                            godot_print!("Disconnect signal received, stopping room task");
                             break;
                         }
+                        RoomExit::Lost(reason) => {
+                            disconnect_reason = reason;
+                            // Kicks, duplicate identities and closed rooms won't be fixed by retrying
+                            if !policy.enabled || !reason.is_retryable() {
+                                break;
+                            }
+                            godot_warn!("LiveKit: Connection lost ({:?}), reconnecting...", reason);
+                            set_state(&connection_state, &event_tx, ConnectionState::Reconnecting);
+                            attempt = 1;
+                        }
                     }
                 }
                 
-                *is_connected.lock().unwrap() = false;
-                event_tx.send(InternalEvent::RoomDisconnected).ok();
+                *shared.mic_source.lock().unwrap() = None;
+                if ever_connected {
+                    event_tx.send(InternalEvent::RoomDisconnected(disconnect_reason)).ok();
+                }
+                // Dropped first so a later disconnect_from_room sees the task as gone
+                drop(disconnect_rx);
+                // Last message from this task, so a new connect can safely follow it
+                if let Some((old, new)) = connection_state.finish(final_state) {
+                    event_tx.send(InternalEvent::StateChanged(old, new)).ok();
+                }
             });
         }
     }
 
+    /// Creates a positional player fed with `identity`'s voice. Add it under
+    /// the participant's avatar so the voice comes from the avatar.
     #[func]
-    pub fn push_mic_audio(&self, buffer: PackedVector2Array) {
-        if let Some(sender) = &self.audio_sender {
-            // buffer is Stereo (Vector2), we need Mono for LiveKit
-            let samples: Vec<f32> = buffer
-                .as_slice()
-                .iter()
-                .map(|v| (v.x + v.y) / 2.0)
-                .collect();
-            
-            sender.send(samples).ok();
-        }
+    pub fn create_participant_audio(&mut self, identity: GString) -> Gd<ParticipantAudio> {
+        let stream = self.create_participant_stream(identity.clone());
+
+        let mut node = ParticipantAudio::new_alloc();
+        node.bind_mut().attach(identity.clone(), stream);
+        node.set_name(&GString::from(format!("Audio_{}", identity)));
+        node
     }
 
+    /// Creates a texture showing `identity`'s video track called `track_name`,
+    /// or any of their video tracks if `track_name` is empty. It stays blank
+    /// until the track is subscribed and updates itself every frame.
     #[func]
-    pub fn send_chat_message(&self, message: GString) {
-        let room = self.room.lock().unwrap();
-        if let Some(room) = room.as_ref() {
-            let room_clone = room.clone();
-            let msg = message.to_string();
-            
-            if let Some(runtime) = &self.runtime {
-                runtime.spawn(async move {
-                    // Use LiveKit's built-in send_chat_message
-                    if let Err(e) = room_clone.local_participant()
-                        .send_chat_message(msg, None, None)
-                        .await
-                    {
-                        godot_error!("Failed to send chat message: {:?}", e);
-                    }
-                });
-            }
-        } else {
-            godot_warn!("Cannot send chat message: not connected to room");
-        }
+    pub fn create_video_texture(
+        &mut self,
+        identity: GString,
+        track_name: GString,
+    ) -> Gd<LiveKitVideoTexture> {
+        let texture = LiveKitVideoTexture::new_for(identity, track_name, &self.video_sinks);
+        self.video_textures.push(texture.clone());
+        texture
     }
 
+    /// Creates an `AudioStream` for `identity`'s voice that can be played by
+    /// any `AudioStreamPlayer`, `AudioStreamPlayer2D` or `AudioStreamPlayer3D`.
+    /// Samples are pulled on the audio thread, so playback keeps its pacing
+    /// when the frame rate dips.
     #[func]
-    pub fn update_username(&self, new_name: GString) {
-        let room = self.room.lock().unwrap();
-        if let Some(room) = room.as_ref() {
-            let room_clone = room.clone();
-            let name = new_name.to_string();
-            
-            if let Some(runtime) = &self.runtime {
-                runtime.spawn(async move {
-                    // Use LocalParticipant.set_metadata to update username
-                    // This triggers RoomEvent::ParticipantMetadataChanged on other clients
-                    let metadata = serde_json::json!({
-                        "username": name
-                    }).to_string();
-                    
-                    if let Err(e) = room_clone.local_participant().set_metadata(metadata).await {
-                        godot_error!("Failed to update username: {:?}", e);
-                    } else {
-                        godot_print!("Username updated to: {}", name);
-                    }
-                });
-            }
-        } else {
-            godot_warn!("Cannot update username: not connected to room");
-        }
+    pub fn create_participant_stream(&self, identity: GString) -> Gd<LiveKitAudioStream> {
+        self.create_participant_track_stream(identity, GString::new())
     }
+
+    /// Like `create_participant_stream`, but for `identity`'s audio track
+    /// called `track_name`, e.g. music they publish with `publish_audio_bus`.
+    /// An empty `track_name` is their voice.
     #[func]
-    pub fn get_local_identity(&self) -> GString {
-        if let Some(room) = self.room.lock().unwrap().as_ref() {
-            return room.local_participant().identity().to_string().into();
-        }
-        "local".into()
+    pub fn create_participant_track_stream(
+        &self,
+        identity: GString,
+        track_name: GString,
+    ) -> Gd<LiveKitAudioStream> {
+        let quality = self.resampler_quality();
+        LiveKitAudioStream::new_for(identity, track_name, self.audio_sinks.clone(), quality)
     }
-}
 
+    /// Plays `identity`'s voice through an existing `AudioStreamPlayer`,
+    /// `AudioStreamPlayer2D` or `AudioStreamPlayer3D` whose stream is an
+    /// `AudioStreamGenerator`. Frames are pushed from Rust every process
+    /// tick, replacing any previous player or `ParticipantAudio` for `identity`.
+    /// The player may be stopped and restarted; freeing it detaches it.
+    #[func]
+    pub fn attach_participant_player(&mut self, identity: GString, mut player: Gd<Node>) -> bool {
+        if !player.has_method("get_stream_playback") {
+            godot_error!("LiveKit: attach_participant_player needs an audio stream player, got {}", player.get_class());
+            return false;
+        }
+
+        // Playback only exists while the player is playing
+        if !player.call("is_playing", &[]).booleanize() {
+            player.call("play", &[]);
+        }
+
+        let playback = player.call("get_stream_playback", &[]);
+        if playback.try_to::<Gd<AudioStreamGeneratorPlayback>>().is_err() {
+            godot_error!("LiveKit: attach_participant_player needs a player with an AudioStreamGenerator stream");
+            return false;
+        }
+
+        // Generators play at their own mix rate, which needn't match the output's
+        let sample_rate = player
+            .get("stream")
+            .try_to::<Gd<AudioStreamGenerator>>()
+            .map(|generator| generator.get_mix_rate())
+            .unwrap_or_else(|_| AudioServer::singleton().get_mix_rate()) as u32;
+        let quality = self.resampler_quality();
+
+        let identity = identity.to_string();
+        let (sink, receiver) = audio_channel(400, sample_rate, quality);
+        self.audio_sinks.register(&identity, "", &sink);
+        self.attached_players.insert(
+            identity,
+            AttachedPlayer { player, _sink: sink, receiver, sample_rate },
+        );
+        true
+    }
+
+    /// Asks the server for a simulcast layer of `identity`'s video track called
+    /// `track_name`, or all their video tracks if it is empty. Use
+    /// `VIDEO_QUALITY_LOW` for far-away screens. Only tracks published with
+    /// simulcast have layers to pick from. Remembered for tracks subscribed
+    /// later and after reconnects.
+    #[func]
+    pub fn set_video_quality(&self, identity: GString, track_name: GString, quality: i32) {
+        let Some(quality) = VideoQuality::from_i32(quality) else {
+            godot_warn!("LiveKit: Unknown video quality {}", quality);
+            return;
+        };
+        self.set_video_preference(identity, track_name, VideoPreference::Quality(quality));
+    }
+
+    /// Like `set_video_quality`, but with the size the track is shown at; the
+    /// server sends the smallest layer that covers it.
+    #[func]
+    pub fn set_video_dimensions(&self, identity: GString, track_name: GString, size: Vector2i) {
+        let preference = VideoPreference::Dimensions(size.x.max(1) as u32, size.y.max(1) as u32);
+        self.set_video_preference(identity, track_name, preference);
+    }
+
+    /// Subscribes to or unsubscribes from `identity`'s audio tracks.
+    /// Unsubscribed voices are neither downloaded nor decoded. The choice is
+    /// remembered, so it also applies to tracks published later and after a
+    /// reconnect; everyone else follows `auto_subscribe`.
+    #[func]
+    pub fn set_participant_subscribed(&mut self, identity: GString, subscribed: bool) {
+        let identity = identity.to_string();
+        self.subscriptions.lock().unwrap().insert(identity.clone(), subscribed);
+
+        let room = self.room.lock().unwrap().clone();
+        if let (Some(room), Some(runtime)) = (room, &self.runtime) {
+            // The subscription update is sent from a spawned task
+            let _guard = runtime.enter();
+            let participants = room.remote_participants();
+            if let Some(participant) = participants.values().find(|p| p.identity().as_str() == identity) {
+                set_audio_subscribed(participant, subscribed);
+            }
+        }
+    }
+
+    /// Participants currently speaking, loudest first.
+    #[func]
+    pub fn get_active_speakers(&self) -> PackedStringArray {
+        self.active_speakers
+            .iter()
+            .map(|identity| GString::from(identity.as_str()))
+            .collect()
+    }
+
+    #[func]
+    pub fn detach_participant_player(&mut self, identity: GString) {
+        self.attached_players.remove(&identity.to_string());
+    }
+
+    /// Jitter buffer statistics for `identity`'s current player: `depth_ms`,
+    /// `target_ms`, `underruns`, `overruns`, `concealed_ms` and `dropped_ms`.
+    /// Empty if nothing is playing the participant.
+    #[func]
+    pub fn get_audio_stats(&self, identity: GString) -> Dictionary {
+        let mut stats = Dictionary::new();
+        if let Some(sink) = self.audio_sinks.get(&identity.to_string(), "") {
+            let snapshot = sink.stats().snapshot();
+            stats.set("depth_ms", snapshot.depth_ms);
+            stats.set("target_ms", snapshot.target_ms);
+            stats.set("underruns", snapshot.underruns as i64);
+            stats.set("overruns", snapshot.overruns as i64);
+            stats.set("concealed_ms", snapshot.concealed_ms);
+            stats.set("dropped_ms", snapshot.dropped_ms);
+        }
+        stats
+    }
+
+    /// Enables the per-frame `on_audio_frame` signal. Off by default; only
+    /// needed for visualisation now that playback is fed from Rust.
+    #[func]
+    pub fn set_emit_audio_frames(&mut self, enabled: bool) {
+        self.emit_audio_frames.store(enabled, Ordering::Relaxed);
+    }
+
+    #[func]
+    pub fn get_emit_audio_frames(&self) -> bool {
+        self.emit_audio_frames.load(Ordering::Relaxed)
+    }
+
+    /// Captures the default input device and publishes it, replacing manual
+    /// `push_mic_audio` calls. Creates `bus_name` if needed (muted, so the
+    /// player doesn't hear themselves) with an `AudioEffectCapture` on it, plus
+    /// an `AudioStreamMicrophone` player routed to it.
+    #[func]
+    pub fn start_microphone(&mut self, bus_name: GString) -> bool {
+        let enable_input = ProjectSettings::singleton().get_setting("audio/driver/enable_input");
+        if !enable_input.booleanize() {
+            godot_warn!("LiveKit: audio/driver/enable_input is off, the microphone will be silent");
+        }
+
+        self.stop_microphone();
+
+        let bus_name = bus_name.to_string();
+        let bus_index = AudioHandler::setup_capture_bus(&bus_name);
+        let mut capture = AudioHandler::new();
+        if !capture.init_capture(bus_index) {
+            return false;
+        }
+
+        // A running session's feeder follows the rate sent with each capture
+        self.mic_sample_rate = AudioServer::singleton().get_mix_rate() as i32;
+
+        let mut player = AudioStreamPlayer::new_gd();
+        player.set_stream(&AudioStreamMicrophone::new_gd());
+        player.set_bus(bus_name.as_str());
+        player.set_name("LiveKitMicrophone");
+        self.base_mut().add_child(&player);
+        player.play();
+
+        self.mic_capture = Some(capture);
+        self.mic_player = Some(player);
+        godot_print!("LiveKit: Microphone capture started on bus '{}'", bus_name);
+        true
+    }
+
+    #[func]
+    pub fn stop_microphone(&mut self) {
+        self.mic_capture = None;
+        if let Some(mut player) = self.mic_player.take() {
+            player.stop();
+            player.queue_free();
+        }
+    }
+
+    #[func]
+    pub fn is_microphone_started(&self) -> bool {
+        self.mic_capture.is_some()
+    }
+
+    /// Mutes the published mic track so other participants see it as muted.
+    /// The setting is kept across reconnects and applies to the next
+    /// connection if called while disconnected.
+    #[func]
+    pub fn set_microphone_muted(&mut self, muted: bool) {
+        if self.mic_muted.swap(muted, Ordering::Relaxed) == muted {
+            return;
+        }
+        self.sync_mic_track_mute();
+
+        self.base_mut()
+            .emit_signal("local_mute_changed", &[muted.to_variant()]);
+    }
+
+    #[func]
+    pub fn is_microphone_muted(&self) -> bool {
+        self.mic_muted.load(Ordering::Relaxed)
+    }
+
+    /// Sets echo cancellation, noise suppression, gain control, queue size
+    /// and channel count for the mic. A session in progress switches to a
+    /// new source and republishes the mic track. Later edits to `options`
+    /// need another call to take effect.
+    #[func]
+    pub fn set_microphone_options(&mut self, options: Gd<LiveKitAudioOptions>) {
+        let options = options.bind().track_options();
+        if options == self.mic_options {
+            return;
+        }
+        self.mic_options = options;
+
+        let mic = {
+            let mut mic_source = self.mic_source.lock().unwrap();
+            if mic_source.is_none() {
+                return; // No session; used on the next connect
+            }
+            let mic = MicSource::new(options);
+            *mic_source = Some(mic.clone());
+            mic
+        };
+
+        // While reconnecting the session task publishes the new source itself
+        let Some(room) = self.room.lock().unwrap().clone() else {
+            return;
+        };
+        let Some(runtime) = &self.runtime else {
+            return;
+        };
+
+        let mic_track = self.mic_track.clone();
+        let mic_muted = self.mic_muted.clone();
+        let mic_gate = self.mic_gate.clone();
+        runtime.spawn(async move {
+            let old_track = mic_track.lock().unwrap().take();
+            if let Some(old_track) = old_track {
+                if let Err(e) = room.local_participant().unpublish_track(&old_track.sid()).await {
+                    godot_warn!("LiveKit: Failed to unpublish old mic track: {}", e);
+                }
+            }
+
+            match publish_microphone(&room, &mic).await {
+                Ok(track) => {
+                    let mut mic_track = mic_track.lock().unwrap();
+                    apply_track_mute(&track, &mic_muted, &mic_gate);
+                    *mic_track = Some(track);
+                    godot_print!("LiveKit: Mic republished with new options");
+                }
+                Err(e) => godot_error!("LiveKit: Failed to republish mic: {}", e),
+            }
+        });
+    }
+
+    #[func]
+    pub fn get_microphone_options(&self) -> Gd<LiveKitAudioOptions> {
+        LiveKitAudioOptions::from_track_options(self.mic_options)
+    }
+
+    /// Chooses what opens the mic: `VOICE_OPEN_MIC` sends everything,
+    /// `VOICE_ACTIVITY` only sends while speech is detected and
+    /// `VOICE_PUSH_TO_TALK` only while `push_to_talk_action` is held. In
+    /// push-to-talk the published track is muted while the key is up.
+    #[func]
+    pub fn set_voice_activation_mode(&mut self, mode: i32) {
+        let Some(mode) = VoiceActivationMode::from_i32(mode) else {
+            godot_error!("LiveKit: Unknown voice activation mode {}", mode);
+            return;
+        };
+        if mode == VoiceActivationMode::PushToTalk {
+            let action = StringName::from(&self.push_to_talk_action);
+            if !InputMap::singleton().has_action(&action) {
+                godot_warn!("LiveKit: Push-to-talk action '{}' is not in the InputMap", action);
+            }
+        }
+
+        self.talk_tail_remaining = 0.0;
+        self.mic_gate.update(|settings| {
+            settings.mode = mode;
+            settings.talk_key_open = false;
+        });
+        self.sync_mic_track_mute();
+    }
+
+    #[func]
+    pub fn get_voice_activation_mode(&self) -> i32 {
+        self.mic_gate.settings().mode as i32
+    }
+
+    /// Level, in dBFS, a 10ms mic frame must reach to count as speech.
+    #[func]
+    pub fn set_vad_threshold_db(&mut self, threshold_db: f32) {
+        self.mic_gate.update(|settings| settings.vad.threshold_db = threshold_db);
+    }
+
+    #[func]
+    pub fn get_vad_threshold_db(&self) -> f32 {
+        self.mic_gate.settings().vad.threshold_db
+    }
+
+    /// How long speech must last before the mic counts as speaking.
+    #[func]
+    pub fn set_vad_attack_ms(&mut self, attack_ms: i32) {
+        self.mic_gate.update(|settings| settings.vad.attack_ms = attack_ms.max(0) as u32);
+    }
+
+    #[func]
+    pub fn get_vad_attack_ms(&self) -> i32 {
+        self.mic_gate.settings().vad.attack_ms as i32
+    }
+
+    /// How long the mic stays open after speech stops.
+    #[func]
+    pub fn set_vad_release_ms(&mut self, release_ms: i32) {
+        self.mic_gate.update(|settings| settings.vad.release_ms = release_ms.max(0) as u32);
+    }
+
+    #[func]
+    pub fn get_vad_release_ms(&self) -> i32 {
+        self.mic_gate.settings().vad.release_ms as i32
+    }
+
+    /// Whether silence is sent while the mic is gated, rather than nothing.
+    #[func]
+    pub fn set_send_silence_when_gated(&mut self, enabled: bool) {
+        self.mic_gate.update(|settings| settings.send_silence = enabled);
+    }
+
+    #[func]
+    pub fn get_send_silence_when_gated(&self) -> bool {
+        self.mic_gate.settings().send_silence
+    }
+
+    /// Level of the last 10ms of mic audio as `rms` and `peak` (linear, 0 to
+    /// 1), measured before voice activation gating. Zero while not connected.
+    #[func]
+    pub fn get_local_audio_level(&self) -> Dictionary {
+        level_dictionary(self.local_level.rms(), self.local_level.peak())
+    }
+
+    /// Level of the last 10ms decoded from `identity` as `rms` and `peak`.
+    /// Zero if the participant isn't subscribed.
+    #[func]
+    pub fn get_participant_audio_level(&self, identity: GString) -> Dictionary {
+        match self.participant_levels.get(&identity.to_string()) {
+            Some(level) => level_dictionary(level.rms(), level.peak()),
+            None => level_dictionary(0.0, 0.0),
+        }
+    }
+
+    /// Whether the voice activity detector currently hears speech on the mic.
+    #[func]
+    pub fn is_local_speaking(&self) -> bool {
+        self.mic_gate.is_speaking()
+    }
+
+    #[func]
+    pub fn push_mic_audio(&self, buffer: PackedVector2Array) {
+        if let Some(sender) = &self.audio_sender {
+            // Interleave L/R; the feeder mixes down for a mono source
+            let samples: Vec<f32> = buffer
+                .as_slice()
+                .iter()
+                .flat_map(|v| [v.x, v.y])
+                .collect();
+            
+            sender.send((self.mic_sample_rate as u32, samples)).ok();
+        }
+    }
+
+    /// Publishes everything played on the audio bus `bus_name` as a track
+    /// called `track_name`, e.g. music or a media player. The bus keeps
+    /// playing locally. `options` defaults to stereo without voice
+    /// processing or DTX. The track stays published across reconnects and
+    /// sessions until `unpublish_track` is called.
+    #[func]
+    pub fn publish_audio_bus(
+        &mut self,
+        bus_name: GString,
+        track_name: GString,
+        options: Option<Gd<LiveKitAudioOptions>>,
+    ) -> bool {
+        let Some(runtime) = &self.runtime else {
+            godot_error!("LiveKit: publish_audio_bus called before the node was ready");
+            return false;
+        };
+
+        let track_name = track_name.to_string();
+        if track_name.is_empty() || self.local_tracks.contains(&track_name) {
+            godot_warn!("LiveKit: Track name '{}' is empty or already in use", track_name);
+            return false;
+        }
+
+        let mut audio_server = AudioServer::singleton();
+        let bus_index = audio_server.get_bus_index(&StringName::from(&bus_name));
+        if bus_index < 0 {
+            godot_warn!("LiveKit: No audio bus named '{}'", bus_name);
+            return false;
+        }
+        AudioHandler::add_capture_effect(bus_index);
+        let mut capture = AudioHandler::new();
+        if !capture.init_capture(bus_index) {
+            return false;
+        }
+        let already_captured = self
+            .mic_capture
+            .iter()
+            .chain(self.bus_captures.values().map(|bus| &bus.capture))
+            .any(|other| other.shares_capture_with(&capture));
+        if already_captured {
+            godot_warn!("LiveKit: Bus '{}' is already being captured", bus_name);
+            return false;
+        }
+
+        let options = options
+            .map(|options| options.bind().track_options())
+            .unwrap_or_else(AudioTrackOptions::music);
+        let source = options.create_source();
+        let chunker = FrameChunker::new(
+            audio_server.get_mix_rate() as u32,
+            self.resampler_quality(),
+        );
+        let (sender, audio_rx) = mpsc::unbounded_channel::<Vec<f32>>();
+        runtime.spawn(feed_source(source.clone(), options.channels, audio_rx, chunker));
+
+        self.add_local_track(&track_name, LocalSource::Audio(source, options));
+        self.bus_captures.insert(track_name.clone(), BusCapture { capture, sender });
+        godot_print!("LiveKit: Publishing bus '{}' as track '{}'", bus_name, track_name);
+        true
+    }
+
+    /// Publishes what `viewport` renders as a video track called `track_name`,
+    /// e.g. a spectator camera in a `SubViewport`. Frames are read back at
+    /// `fps` and scaled to `resolution`; a zero resolution uses the viewport's
+    /// current size. `options` may be null for simulcast VP8. The track stays
+    /// published across reconnects and sessions until `unpublish_track` is
+    /// called or the viewport is freed.
+    #[func]
+    pub fn publish_viewport(
+        &mut self,
+        viewport: Gd<Viewport>,
+        track_name: GString,
+        fps: i32,
+        resolution: Vector2i,
+        options: Option<Gd<LiveKitVideoOptions>>,
+    ) -> bool {
+        let Some(runtime) = &self.runtime else {
+            godot_error!("LiveKit: publish_viewport called before the node was ready");
+            return false;
+        };
+
+        let track_name = track_name.to_string();
+        if track_name.is_empty() || self.local_tracks.contains(&track_name) {
+            godot_warn!("LiveKit: Track name '{}' is empty or already in use", track_name);
+            return false;
+        }
+
+        let size = if resolution.x > 0 && resolution.y > 0 {
+            resolution
+        } else {
+            viewport.get_visible_rect().size.cast_int()
+        };
+        if size.x < 2 || size.y < 2 {
+            godot_warn!("LiveKit: Viewport for track '{}' has no size", track_name);
+            return false;
+        }
+
+        let options = LiveKitVideoOptions::resolve(options, size.x as u32, size.y as u32, fps as f64);
+        let source = options.create_source();
+        let (sender, frame_rx) = mpsc::unbounded_channel::<VideoInput>();
+        runtime.spawn(feed_video_source(source.clone(), options, frame_rx));
+
+        self.add_local_track(&track_name, LocalSource::Video(source, options));
+        self.viewport_captures.insert(
+            track_name.clone(),
+            ViewportCapture {
+                viewport,
+                interval: 1.0 / options.fps,
+                elapsed: 0.0,
+                sender,
+            },
+        );
+        godot_print!(
+            "LiveKit: Publishing viewport as track '{}' ({}x{} at {} fps)",
+            track_name,
+            options.width,
+            options.height,
+            options.fps
+        );
+        true
+    }
+
+    /// Publishes images pushed to `source` as a video track called
+    /// `track_name`. `options` may be null for simulcast VP8. Like other extra
+    /// tracks it stays published across reconnects and sessions until
+    /// `unpublish_track` is called.
+    #[func]
+    pub fn publish_video_source(
+        &mut self,
+        mut source: Gd<LiveKitVideoSource>,
+        track_name: GString,
+        options: Option<Gd<LiveKitVideoOptions>>,
+    ) -> bool {
+        let Some(runtime) = &self.runtime else {
+            godot_error!("LiveKit: publish_video_source called before the node was ready");
+            return false;
+        };
+
+        let track_name = track_name.to_string();
+        if track_name.is_empty() || self.local_tracks.contains(&track_name) {
+            godot_warn!("LiveKit: Track name '{}' is empty or already in use", track_name);
+            return false;
+        }
+        if source.bind().is_published() {
+            godot_warn!("LiveKit: Video source is already published");
+            return false;
+        }
+
+        let options = source.bind().track_options(options);
+        let native_source = options.create_source();
+        let (sender, frame_rx) = mpsc::unbounded_channel::<VideoInput>();
+        runtime.spawn(feed_video_source(native_source.clone(), options, frame_rx));
+        source.bind_mut().set_sender(Some(sender));
+
+        self.add_local_track(&track_name, LocalSource::Video(native_source, options));
+        self.video_sources.insert(track_name.clone(), source);
+        godot_print!(
+            "LiveKit: Publishing video source as track '{}' ({}x{})",
+            track_name,
+            options.width,
+            options.height
+        );
+        true
+    }
+
+    /// Stops publishing a track added with `publish_audio_bus`,
+    /// `publish_viewport` or `publish_video_source`. Returns false if no such
+    /// track exists.
+    #[func]
+    pub fn unpublish_track(&mut self, track_name: GString) -> bool {
+        let track_name = track_name.to_string();
+        let bus = self.bus_captures.remove(&track_name);
+        let viewport = self.viewport_captures.remove(&track_name);
+        let video_source = self.video_sources.remove(&track_name);
+        if bus.is_none() && viewport.is_none() && video_source.is_none() {
+            return false;
+        }
+        if let Some(mut source) = video_source {
+            source.bind_mut().set_sender(None);
+        }
+
+        let track = self.local_tracks.remove(&track_name);
+        let room = self.room.lock().unwrap().clone();
+        if let (Some(track), Some(room), Some(runtime)) = (track, room, &self.runtime) {
+            runtime.spawn(async move {
+                if let Err(e) = room.local_participant().unpublish_track(&track.sid()).await {
+                    godot_warn!("LiveKit: Failed to unpublish track '{}': {}", track.name(), e);
+                }
+            });
+        }
+        true
+    }
+
+    #[func]
+    pub fn send_chat_message(&self, message: GString) {
+        let room = self.room.lock().unwrap();
+        if let Some(room) = room.as_ref() {
+            let room_clone = room.clone();
+            let msg = message.to_string();
+            
+            if let Some(runtime) = &self.runtime {
+                runtime.spawn(async move {
+                    // Use LiveKit's built-in send_chat_message
+                    if let Err(e) = room_clone.local_participant()
+                        .send_chat_message(msg, None, None)
+                        .await
+                    {
+                        godot_error!("Failed to send chat message: {:?}", e);
+                    }
+                });
+            }
+        } else {
+            godot_warn!("Cannot send chat message: not connected to room");
+        }
+    }
+
+    /// Publishes `payload` on the room's data channel. An empty
+    /// `destination_identities` broadcasts to everyone in the room.
+    #[func]
+    pub fn send_data(
+        &self,
+        payload: PackedByteArray,
+        topic: GString,
+        reliable: bool,
+        destination_identities: PackedStringArray,
+    ) {
+        let room = self.room.lock().unwrap();
+        if let Some(room) = room.as_ref() {
+            let room_clone = room.clone();
+            let topic = topic.to_string();
+            let packet = DataPacket {
+                payload: payload.to_vec(),
+                topic: (!topic.is_empty()).then_some(topic),
+                reliable,
+                destination_identities: destination_identities
+                    .as_slice()
+                    .iter()
+                    .map(|identity| identity.to_string().into())
+                    .collect(),
+            };
+
+            if let Some(runtime) = &self.runtime {
+                runtime.spawn(async move {
+                    if let Err(e) = room_clone.local_participant().publish_data(packet).await {
+                        godot_error!("Failed to send data: {:?}", e);
+                    }
+                });
+            }
+        } else {
+            godot_warn!("Cannot send data: not connected to room");
+        }
+    }
+
+    #[func]
+    pub fn update_username(&self, new_name: GString) {
+        let room = self.room.lock().unwrap();
+        if let Some(room) = room.as_ref() {
+            let room_clone = room.clone();
+            let name = new_name.to_string();
+            
+            if let Some(runtime) = &self.runtime {
+                runtime.spawn(async move {
+                    // Use LocalParticipant.set_metadata to update username
+                    // This triggers RoomEvent::ParticipantMetadataChanged on other clients
+                    let metadata = serde_json::json!({
+                        "username": name
+                    }).to_string();
+                    
+                    if let Err(e) = room_clone.local_participant().set_metadata(metadata).await {
+                        godot_error!("Failed to update username: {:?}", e);
+                    } else {
+                        godot_print!("Username updated to: {}", name);
+                    }
+                });
+            }
+        } else {
+            godot_warn!("Cannot update username: not connected to room");
+        }
+    }
+    #[func]
+    pub fn get_local_identity(&self) -> GString {
+        if let Some(room) = self.room.lock().unwrap().as_ref() {
+            return room.local_participant().identity().to_string().into();
+        }
+        "local".into()
+    }
+}
+
+/// State the session task shares with the node.
+#[derive(Clone)]
+struct SessionShared {
+    connection_state: SharedConnectionState,
+    audio_sinks: RemoteAudioSinks,
+    video_sinks: RemoteVideoSinks,
+    video_preferences: VideoPreferences,
+    emit_audio_frames: Arc<AtomicBool>,
+    mic_source: Arc<Mutex<Option<MicSource>>>,
+    mic_track: Arc<Mutex<Option<LocalAudioTrack>>>,
+    mic_muted: Arc<AtomicBool>,
+    subscriptions: Arc<Mutex<HashMap<String, bool>>>,
+    mic_gate: SharedMicGate,
+    participant_levels: AudioLevels,
+    local_tracks: LocalTracks,
+}
+
+/// Transitions the shared state from the session task and queues the signal
+/// for the main thread.
+fn set_state(
+    state: &SharedConnectionState,
+    event_tx: &mpsc::UnboundedSender<InternalEvent>,
+    new: ConnectionState,
+) {
+    if let Some(old) = state.transition(new) {
+        event_tx.send(InternalEvent::StateChanged(old, new)).ok();
+    }
+}
+
+/// Why a single room connection ended.
+enum RoomExit {
+    /// `disconnect_from_room` was called.
+    Requested,
+    /// The server dropped us or the room event stream ended.
+    Lost(ErrorCode),
+}
+
+/// Mutes `track` if the user muted the mic or push-to-talk is holding it closed.
+fn apply_track_mute(track: &LocalAudioTrack, mic_muted: &AtomicBool, gate: &SharedMicGate) {
+    let muted = mic_muted.load(Ordering::Relaxed) || gate.settings().mutes_track();
+    if track.is_muted() != muted {
+        if muted {
+            track.mute();
+        } else {
+            track.unmute();
+        }
+    }
+}
+
+/// The mic's current source and the options it was created with.
+#[derive(Clone)]
+struct MicSource {
+    source: NativeAudioSource,
+    options: AudioTrackOptions,
+}
+
+impl MicSource {
+    fn new(options: AudioTrackOptions) -> Self {
+        Self { source: options.create_source(), options }
+    }
+}
+
+/// Wraps the long-lived mic source in a new track and publishes it to `room`.
+async fn publish_microphone(room: &Room, mic: &MicSource) -> Result<LocalAudioTrack, RoomError> {
+    publish_source(room, "mic", &mic.source, &mic.options, TrackSource::Microphone).await
+}
+
+/// Feeds pushed mic samples to the current source in 10ms frames, holding
+/// back frames while the voice activation gate is closed. Each batch carries
+/// its capture rate, so the mic can be (re)started while connected.
+async fn feed_microphone(
+    mic_source: Arc<Mutex<Option<MicSource>>>,
+    mut audio_rx: mpsc::UnboundedReceiver<(u32, Vec<f32>)>,
+    mut chunker: FrameChunker,
+    gate: SharedMicGate,
+    level: Arc<AudioLevel>,
+    event_tx: mpsc::UnboundedSender<InternalEvent>,
+) {
+    let mut vad = VoiceActivityDetector::default();
+
+    while let Some((rate, samples)) = audio_rx.recv().await {
+        chunker.set_input_rate(rate);
+        chunker.push(&samples);
+
+        while let Some(mut chunk) = chunker.next_chunk() {
+            let (rms, peak) = measure(&chunk);
+            level.store(rms, peak);
+
+            let settings = gate.settings();
+            let speaking = vad.process(rms, 10, &settings.vad);
+            if gate.set_speaking(speaking) {
+                event_tx.send(InternalEvent::LocalSpeakingChanged(speaking)).ok();
+            }
+
+            let open = match settings.mode {
+                VoiceActivationMode::OpenMic => true,
+                VoiceActivationMode::VoiceActivity => speaking,
+                VoiceActivationMode::PushToTalk => settings.talk_key_open,
+            };
+            if !open {
+                if !settings.send_silence {
+                    continue;
+                }
+                chunk.fill(0);
+            }
+
+            let Some(MicSource { source, options }) = mic_source.lock().unwrap().clone() else {
+                continue;
+            };
+            capture_chunk(&source, chunk, options.channels).await;
+        }
+    }
+
+    // The session ended; nobody is speaking on this mic any more
+    level.reset();
+    if gate.set_speaking(false) {
+        event_tx.send(InternalEvent::LocalSpeakingChanged(false)).ok();
+    }
+}
+
+fn set_audio_subscribed(participant: &RemoteParticipant, subscribed: bool) {
+    for publication in participant.track_publications().values() {
+        if publication.kind() == TrackKind::Audio {
+            publication.set_subscribed(subscribed);
+        }
+    }
+}
+
+/// Reapplies `set_participant_subscribed` overrides to everyone in `room`.
+fn apply_subscriptions(room: &Room, subscriptions: &Mutex<HashMap<String, bool>>) {
+    let subscriptions = subscriptions.lock().unwrap();
+    for participant in room.remote_participants().values() {
+        if let Some(&subscribed) = subscriptions.get(participant.identity().as_str()) {
+            set_audio_subscribed(participant, subscribed);
+        }
+    }
+}
+
+/// Decoding tasks for subscribed remote audio tracks, stopped when the track
+/// is unsubscribed or the room connection ends. Stopping a task zeroes the
+/// level it was metering.
+#[derive(Default)]
+struct DecoderTasks(HashMap<TrackSid, (JoinHandle<()>, Option<Arc<AudioLevel>>)>);
+
+impl DecoderTasks {
+    /// `level` is the audio level the task writes, reset when it stops.
+    fn insert(&mut self, sid: TrackSid, task: JoinHandle<()>, level: Option<Arc<AudioLevel>>) {
+        if let Some((old, _)) = self.0.insert(sid, (task, level)) {
+            old.abort();
+        }
+    }
+
+    fn stop(&mut self, sid: &TrackSid) {
+        if let Some((task, level)) = self.0.remove(sid) {
+            task.abort();
+            if let Some(level) = level {
+                level.reset();
+            }
+        }
+    }
+}
+
+impl Drop for DecoderTasks {
+    fn drop(&mut self) {
+        for (task, level) in self.0.values() {
+            task.abort();
+            if let Some(level) = level {
+                level.reset();
+            }
+        }
+    }
+}
+
+/// Reads back the viewport's current frame as RGBA8.
+fn read_viewport(viewport: &Gd<Viewport>) -> Option<RgbaFrame> {
+    let image = viewport.get_texture()?.get_image()?;
+    image_to_rgba(&image)
+}
+
+fn level_dictionary(rms: f32, peak: f32) -> Dictionary {
+    let mut level = Dictionary::new();
+    level.set("rms", rms);
+    level.set("peak", peak);
+    level
+}
+
+/// Maps a connection quality to its `QUALITY_*` value; the constants are
+/// defined with it so the two cannot drift.
+const fn quality_code(quality: ConnectionQuality) -> i32 {
+    match quality {
+        ConnectionQuality::Excellent => 0,
+        ConnectionQuality::Good => 1,
+        ConnectionQuality::Poor => 2,
+        ConnectionQuality::Lost => 3,
+    }
+}
+
+/// Pulls the "username" field out of participant metadata JSON.
+fn username_from_metadata(metadata: &str) -> Option<String> {
+    if metadata.is_empty() {
+        return None;
+    }
+    let json = serde_json::from_str::<serde_json::Value>(metadata).ok()?;
+    json.get("username").and_then(|v| v.as_str()).map(str::to_string)
+}
+
+/// Reconciles `known` with the participants currently in `room`, emitting
+/// joined/left events for the difference. After a reconnect this reports
+/// anyone who came or went while we were offline instead of replaying
+/// `participant_joined` for everyone.
+fn sync_participants(
+    room: &Room,
+    known: &mut HashSet<String>,
+    event_tx: &mpsc::UnboundedSender<InternalEvent>,
+) {
+    let participants = room.remote_participants();
+    let current: HashSet<String> = participants
+        .values()
+        .map(|p| p.identity().to_string())
+        .collect();
+
+    for gone in known.difference(&current) {
+        event_tx.send(InternalEvent::ParticipantLeft(gone.clone())).ok();
+    }
+
+    for participant in participants.values() {
+        let identity = participant.identity().to_string();
+        if !known.contains(&identity) {
+            event_tx.send(InternalEvent::ParticipantJoined(identity.clone())).ok();
+        }
+
+        // Check for existing metadata
+        if let Some(username) = username_from_metadata(&participant.metadata()) {
+            event_tx
+                .send(InternalEvent::ParticipantMetadataChanged(identity, username))
+                .ok();
+        }
+    }
+
+    *known = current;
+}
+
+/// Forwards room events to the main thread until the room goes away or a
+/// disconnect is requested.
+async fn run_room_events(
+    mut room_events: mpsc::UnboundedReceiver<RoomEvent>,
+    disconnect_rx: &mut tokio::sync::oneshot::Receiver<()>,
+    known_participants: &mut HashSet<String>,
+    shared: &SessionShared,
+    event_tx: &mpsc::UnboundedSender<InternalEvent>,
+) -> RoomExit {
+    let mut decoders = DecoderTasks::default();
+    loop {
+        tokio::select! {
+            event = room_events.recv() => {
+                let Some(event) = event else {
+                    return RoomExit::Lost(ErrorCode::ConnectionLost);
+                };
+
+                match event {
+                    RoomEvent::ParticipantConnected(p) => {
+                        let identity = p.identity().to_string();
+                        known_participants.insert(identity.clone());
+                        event_tx
+                            .send(InternalEvent::ParticipantJoined(identity))
+                            .ok();
+                    }
+                    RoomEvent::ParticipantDisconnected(p) => {
+                        let identity = p.identity().to_string();
+                        known_participants.remove(&identity);
+                        shared.participant_levels.remove(&identity);
+                        event_tx
+                            .send(InternalEvent::ParticipantLeft(identity))
+                            .ok();
+                    }
+                    RoomEvent::TrackPublished { publication, participant } => {
+                        let subscribed = shared
+                            .subscriptions
+                            .lock()
+                            .unwrap()
+                            .get(participant.identity().as_str())
+                            .copied();
+                        if let Some(subscribed) = subscribed {
+                            if publication.kind() == TrackKind::Audio {
+                                publication.set_subscribed(subscribed);
+                            }
+                        }
+                    }
+                    RoomEvent::TrackUnsubscribed { publication, participant, .. } => {
+                        decoders.stop(&publication.sid());
+                        if publication.kind() == TrackKind::Video {
+                            event_tx
+                                .send(InternalEvent::VideoTrackUnsubscribed(
+                                    participant.identity().to_string(),
+                                    publication.name(),
+                                ))
+                                .ok();
+                        }
+                    }
+                    RoomEvent::TrackSubscribed {
+                        track,
+                        publication,
+                        participant,
+                    } => {
+                        if let RemoteTrack::Audio(audio_track) = track {
+                            let event_tx_clone = event_tx.clone();
+                            let audio_sinks = shared.audio_sinks.clone();
+                            let emit_audio_frames = shared.emit_audio_frames.clone();
+                            let participant_id = participant.identity().to_string();
+
+                            // Only the microphone is the participant's voice; other audio
+                            // tracks (e.g. published buses) get their own sinks and no level
+                            let is_voice = publication.source() == TrackSource::Microphone;
+                            let sink_name = if is_voice { String::new() } else { publication.name() };
+                            let level = is_voice
+                                .then(|| shared.participant_levels.slot(&participant_id));
+                            let task_level = level.clone();
+                            let mut stream = NativeAudioStream::new(
+                                audio_track.rtc_track(),
+                                REMOTE_SAMPLE_RATE as i32,
+                                REMOTE_CHANNELS as i32,
+                            );
+
+                            let task = tokio::spawn(async move {
+                                while let Some(frame) = stream.next().await {
+                                    let channels = frame.num_channels.max(1) as usize;
+
+                                    // Feed the track's player directly if one is attached
+                                    if let Some(sink) = audio_sinks.get(&participant_id, &sink_name) {
+                                        sink.push_interleaved(&frame.data, channels);
+                                    }
+
+                                    let Some(task_level) = &task_level else {
+                                        continue;
+                                    };
+                                    task_level.update(&frame.data);
+
+                                    if !emit_audio_frames.load(Ordering::Relaxed) {
+                                        continue;
+                                    }
+
+                                    // Godot expects PackedVector2Array for stereo audio
+                                    let godot_frame: Vec<Vector2> = frame
+                                        .data
+                                        .chunks_exact(channels)
+                                        .map(|samples| {
+                                            let left = samples[0] as f32 / 32768.0;
+                                            let right = samples.get(1).map_or(left, |&s| s as f32 / 32768.0);
+                                            Vector2::new(left, right)
+                                        })
+                                        .collect();
+
+                                    event_tx_clone
+                                        .send(InternalEvent::AudioFrame(
+                                            participant_id.clone(),
+                                            godot_frame,
+                                        ))
+                                        .ok();
+                                }
+                            });
+                            decoders.insert(publication.sid(), task, level);
+                        } else if let RemoteTrack::Video(video_track) = track {
+                            let video_sinks = shared.video_sinks.clone();
+                            let participant_id = participant.identity().to_string();
+                            let track_name = publication.name();
+                            let mut stream = NativeVideoStream::new(video_track.rtc_track());
+
+                            shared.video_preferences.apply(&participant_id, &publication);
+
+                            event_tx
+                                .send(InternalEvent::VideoTrackSubscribed(
+                                    participant_id.clone(),
+                                    track_name.clone(),
+                                ))
+                                .ok();
+
+                            let task = tokio::spawn(async move {
+                                while let Some(frame) = stream.next().await {
+                                    // Skip the conversion while no texture shows this track
+                                    let slots = video_sinks.slots_for(&participant_id, &track_name);
+                                    if slots.is_empty() {
+                                        continue;
+                                    }
+
+                                    let frame = Arc::new(RgbaFrame::from_video_frame(&frame));
+                                    for slot in slots {
+                                        slot.store(frame.clone());
+                                    }
+                                }
+                            });
+                            decoders.insert(publication.sid(), task, None);
+                        }
+                    }
+                    RoomEvent::ChatMessage { message, participant } => {
+                        // Use built-in ChatMessage event
+                        let sender_identity = participant
+                            .map(|p| p.identity().to_string())
+                            .unwrap_or_else(|| "Unknown".to_string());
+
+                        event_tx
+                            .send(InternalEvent::ChatMessage(
+                                sender_identity,
+                                message.message,
+                                message.timestamp as u64,
+                            ))
+                            .ok();
+                    }
+                    RoomEvent::DataReceived { payload, topic, kind: _, participant } => {
+                        // Packets without a participant were sent by the server API
+                        let sender_identity = participant
+                            .map(|p| p.identity().to_string())
+                            .unwrap_or_default();
+
+                        event_tx
+                            .send(InternalEvent::DataReceived(
+                                sender_identity,
+                                topic.unwrap_or_default(),
+                                payload,
+                            ))
+                            .ok();
+                    }
+                    RoomEvent::ParticipantMetadataChanged { participant, old_metadata: _, metadata } => {
+                        // Extract username from metadata
+                        if let Some(username) = username_from_metadata(&metadata) {
+                            event_tx
+                                .send(InternalEvent::ParticipantMetadataChanged(
+                                    participant.identity().to_string(),
+                                    username,
+                                ))
+                                .ok();
+                        }
+                    }
+                    // Only audio mutes matter for the mute icon
+                    RoomEvent::TrackMuted { participant, publication }
+                        if publication.source() == TrackSource::Microphone =>
+                    {
+                        event_tx
+                            .send(InternalEvent::ParticipantMuted(participant.identity().to_string(), true))
+                            .ok();
+                    }
+                    RoomEvent::TrackUnmuted { participant, publication }
+                        if publication.source() == TrackSource::Microphone =>
+                    {
+                        event_tx
+                            .send(InternalEvent::ParticipantMuted(participant.identity().to_string(), false))
+                            .ok();
+                    }
+                    RoomEvent::ActiveSpeakersChanged { speakers } => {
+                        let identities = speakers.iter().map(|p| p.identity().to_string()).collect();
+                        event_tx.send(InternalEvent::ActiveSpeakersChanged(identities)).ok();
+                    }
+                    RoomEvent::ConnectionQualityChanged { quality, participant } => {
+                        event_tx
+                            .send(InternalEvent::ConnectionQualityChanged(
+                                participant.identity().to_string(),
+                                quality,
+                            ))
+                            .ok();
+                    }
+                    // The SDK resumes short signal drops on its own; surface it as attempt 0
+                    RoomEvent::Reconnecting => {
+                        set_state(&shared.connection_state, event_tx, ConnectionState::Reconnecting);
+                        event_tx.send(InternalEvent::Reconnecting(0)).ok();
+                    }
+                    RoomEvent::Reconnected => {
+                        set_state(&shared.connection_state, event_tx, ConnectionState::Connected);
+                        event_tx.send(InternalEvent::Reconnected).ok();
+                    }
+                    RoomEvent::Disconnected { reason } => {
+                        godot_print!("LiveKit: Room disconnected ({:?})", reason);
+                        return RoomExit::Lost(ErrorCode::from_disconnect_reason(reason));
+                    }
+                    _ => {}
+                }
+            }
+            _ = &mut *disconnect_rx => {
+                godot_print!("Disconnect signal received, stopping room task");
+                return RoomExit::Requested;
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use godot::prelude::*;
+use livekit::{
+    track::{LocalAudioTrack, LocalTrack, TrackSource},
+    webrtc::{
+        audio_frame::AudioFrame, audio_source::native::NativeAudioSource,
+        prelude::RtcAudioSource,
+    },
+    Room,
+};
+use tokio::sync::mpsc;
+
+use crate::audio_options::{AudioTrackOptions, PUBLISH_SAMPLE_RATE};
+use crate::resampler::{Resampler, ResamplerQuality};
+
+/// Frames in one 10ms chunk at `PUBLISH_SAMPLE_RATE`.
+pub const FRAMES_PER_CHUNK: usize = (PUBLISH_SAMPLE_RATE / 100) as usize;
+
+/// Turns interleaved stereo f32 audio captured at any rate into 10ms chunks
+/// of interleaved stereo i16 at `PUBLISH_SAMPLE_RATE`.
+pub struct FrameChunker {
+    input_rate: u32,
+    quality: ResamplerQuality,
+    resampler: Resampler,
+    resampled: Vec<f32>,
+    buffer: Vec<i16>,
+}
+
+impl FrameChunker {
+    pub fn new(input_rate: u32, quality: ResamplerQuality) -> Self {
+        Self {
+            input_rate,
+            quality,
+            resampler: Resampler::new(input_rate, PUBLISH_SAMPLE_RATE, 2, quality),
+            resampled: Vec::new(),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Switches to input captured at `input_rate`, e.g. after the mic was
+    /// restarted on another device. Chunks already converted are kept.
+    pub fn set_input_rate(&mut self, input_rate: u32) {
+        if input_rate != self.input_rate {
+            self.input_rate = input_rate;
+            self.resampler = Resampler::new(input_rate, PUBLISH_SAMPLE_RATE, 2, self.quality);
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        self.resampled.clear();
+        self.resampler.process(samples, &mut self.resampled);
+        self.buffer.extend(
+            self.resampled
+                .iter()
+                .map(|&sample| (sample.clamp(-1.0, 1.0) * 32767.0) as i16),
+        );
+    }
+
+    /// Takes the next complete 10ms chunk, if one is buffered.
+    pub fn next_chunk(&mut self) -> Option<Vec<i16>> {
+        let samples = FRAMES_PER_CHUNK * 2;
+        if self.buffer.len() < samples {
+            return None;
+        }
+        Some(self.buffer.drain(..samples).collect())
+    }
+}
+
+/// Hands a stereo chunk to `source`, mixing it down first for a mono source.
+pub async fn capture_chunk(source: &NativeAudioSource, chunk: Vec<i16>, channels: u32) {
+    let data = if channels == 1 {
+        chunk
+            .chunks_exact(2)
+            .map(|lr| ((lr[0] as i32 + lr[1] as i32) / 2) as i16)
+            .collect()
+    } else {
+        chunk
+    };
+
+    let frame = AudioFrame {
+        data: std::borrow::Cow::Owned(data),
+        sample_rate: PUBLISH_SAMPLE_RATE,
+        num_channels: channels,
+        samples_per_channel: FRAMES_PER_CHUNK as u32,
+    };
+
+    if let Err(e) = source.capture_frame(&frame).await {
+        godot_error!("Failed to capture audio frame: {:?}", e);
+    }
+}
+
+/// Feeds everything sent on `audio_rx` to `source` until the sender is dropped.
+pub async fn feed_source(
+    source: NativeAudioSource,
+    channels: u32,
+    mut audio_rx: mpsc::UnboundedReceiver<Vec<f32>>,
+    mut chunker: FrameChunker,
+) {
+    while let Some(samples) = audio_rx.recv().await {
+        chunker.push(&samples);
+        while let Some(chunk) = chunker.next_chunk() {
+            capture_chunk(&source, chunk, channels).await;
+        }
+    }
+}
+
+/// Wraps `source` in a new track called `name` and publishes it to `room`.
+pub async fn publish_source(
+    room: &Room,
+    name: &str,
+    source: &NativeAudioSource,
+    options: &AudioTrackOptions,
+    kind: TrackSource,
+) -> Result<LocalAudioTrack, livekit::RoomError> {
+    let track = LocalAudioTrack::create_audio_track(name, RtcAudioSource::Native(source.clone()));
+
+    room.local_participant()
+        .publish_track(LocalTrack::Audio(track.clone()), options.publish_options(kind))
+        .await
+        .map(|_| track)
+}
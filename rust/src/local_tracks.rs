@@ -0,0 +1,122 @@
+use godot::prelude::*;
+use livekit::{
+    track::{LocalTrack, TrackSource},
+    webrtc::{audio_source::native::NativeAudioSource, video_source::native::NativeVideoSource},
+    Room, RoomError,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::audio_options::AudioTrackOptions;
+use crate::local_audio::publish_source;
+use crate::local_video::publish_video_track;
+use crate::video_options::VideoTrackOptions;
+
+/// Source behind a track added with `publish_audio_bus`, `publish_viewport`
+/// or `publish_video_source`. It outlives room connections; each (re)connect
+/// wraps it in a new track.
+#[derive(Clone)]
+pub enum LocalSource {
+    Audio(NativeAudioSource, AudioTrackOptions),
+    Video(NativeVideoSource, VideoTrackOptions),
+}
+
+impl LocalSource {
+    async fn publish(&self, room: &Room, name: &str) -> Result<LocalTrack, RoomError> {
+        match self {
+            Self::Audio(source, options) => {
+                publish_source(room, name, source, options, TrackSource::Unknown)
+                    .await
+                    .map(LocalTrack::Audio)
+            }
+            Self::Video(source, options) => publish_video_track(room, name, source, options)
+                .await
+                .map(LocalTrack::Video),
+        }
+    }
+}
+
+struct LocalEntry {
+    source: LocalSource,
+    track: Option<LocalTrack>,
+    publishing: bool,
+}
+
+/// Extra tracks published from Godot, keyed by track name. Shared with the
+/// session task, which republishes them after every (re)connect.
+#[derive(Clone, Default)]
+pub struct LocalTracks(Arc<Mutex<HashMap<String, LocalEntry>>>);
+
+impl LocalTracks {
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.lock().unwrap().contains_key(name)
+    }
+
+    pub fn insert(&self, name: &str, source: LocalSource) {
+        self.0.lock().unwrap().insert(
+            name.to_string(),
+            LocalEntry { source, track: None, publishing: false },
+        );
+    }
+
+    /// Forgets `name`, returning its published track so it can be unpublished.
+    pub fn remove(&self, name: &str) -> Option<LocalTrack> {
+        self.0.lock().unwrap().remove(name).and_then(|entry| entry.track)
+    }
+
+    /// Marks every unpublished track as being published and returns them.
+    /// Claiming under the lock keeps the node and the session task from
+    /// publishing the same track twice.
+    pub fn claim_unpublished(&self) -> Vec<(String, LocalSource)> {
+        let mut tracks = self.0.lock().unwrap();
+        tracks
+            .iter_mut()
+            .filter(|(_, entry)| entry.track.is_none() && !entry.publishing)
+            .map(|(name, entry)| {
+                entry.publishing = true;
+                (name.clone(), entry.source.clone())
+            })
+            .collect()
+    }
+
+    /// Forgets published tracks after the room connection ended.
+    pub fn clear_published(&self) {
+        for entry in self.0.lock().unwrap().values_mut() {
+            entry.track = None;
+            entry.publishing = false;
+        }
+    }
+
+    /// Publishes a claimed track. If it was removed meanwhile, the new track
+    /// is unpublished again.
+    pub async fn publish(self, room: Arc<Room>, name: String, source: LocalSource) {
+        let result = source.publish(&room, &name).await;
+
+        let orphan = {
+            let mut tracks = self.0.lock().unwrap();
+            let entry = tracks.get_mut(&name);
+            match result {
+                Ok(track) => match entry {
+                    Some(entry) => {
+                        godot_print!("LiveKit: Published track '{}'", name);
+                        entry.track = Some(track);
+                        entry.publishing = false;
+                        None
+                    }
+                    None => Some(track),
+                },
+                Err(e) => {
+                    godot_error!("LiveKit: Failed to publish track '{}': {}", name, e);
+                    if let Some(entry) = entry {
+                        entry.publishing = false;
+                    }
+                    None
+                }
+            }
+        };
+
+        if let Some(track) = orphan {
+            room.local_participant().unpublish_track(&track.sid()).await.ok();
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use livekit::{
+    track::{LocalTrack, LocalVideoTrack},
+    webrtc::{
+        native::yuv_helper,
+        prelude::RtcVideoSource,
+        video_frame::{I420Buffer, VideoFrame, VideoRotation},
+        video_source::native::NativeVideoSource,
+    },
+    Room, RoomError,
+};
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+use crate::remote_video::RgbaFrame;
+use crate::video_options::VideoTrackOptions;
+
+/// A frame waiting to be converted and the time it was captured, if the
+/// caller knows it.
+pub struct VideoInput {
+    pub frame: RgbaFrame,
+    pub timestamp_us: Option<i64>,
+}
+
+/// Video counterpart of `local_audio::publish_source`.
+pub async fn publish_video_track(
+    room: &Room,
+    name: &str,
+    source: &NativeVideoSource,
+    options: &VideoTrackOptions,
+) -> Result<LocalVideoTrack, RoomError> {
+    let track = LocalVideoTrack::create_video_track(name, RtcVideoSource::Native(source.clone()));
+
+    room.local_participant()
+        .publish_track(LocalTrack::Video(track.clone()), options.publish_options())
+        .await
+        .map(|_| track)
+}
+
+/// Scales and converts frames sent on `frame_rx` to I420 and hands them to
+/// `source` until the sender is dropped. When conversion falls behind, only
+/// the newest waiting frame is sent. Frames without a timestamp are stamped
+/// with the time since the feeder started.
+pub async fn feed_video_source(
+    source: NativeVideoSource,
+    options: VideoTrackOptions,
+    mut frame_rx: mpsc::UnboundedReceiver<VideoInput>,
+) {
+    let start = Instant::now();
+
+    while let Some(mut input) = frame_rx.recv().await {
+        while let Ok(newer) = frame_rx.try_recv() {
+            input = newer;
+        }
+        let VideoInput { frame, timestamp_us } = input;
+
+        let (width, height) = (options.width, options.height);
+        let rgba = if (frame.width, frame.height) == (width, height) {
+            frame.data
+        } else {
+            scale_rgba(&frame, width, height)
+        };
+
+        let mut buffer = I420Buffer::new(width, height);
+        let (stride_y, stride_u, stride_v) = buffer.strides();
+        let (y, u, v) = buffer.data_mut();
+        // libyuv names formats by 32-bit word, so ABGR is R, G, B, A in memory
+        yuv_helper::abgr_to_i420(
+            &rgba,
+            width * 4,
+            y,
+            stride_y,
+            u,
+            stride_u,
+            v,
+            stride_v,
+            width as i32,
+            height as i32,
+        );
+
+        source.capture_frame(&VideoFrame {
+            rotation: VideoRotation::VideoRotation0,
+            timestamp_us: timestamp_us.unwrap_or_else(|| start.elapsed().as_micros() as i64),
+            buffer,
+        });
+    }
+}
+
+/// Bilinear RGBA resize.
+fn scale_rgba(frame: &RgbaFrame, width: u32, height: u32) -> Vec<u8> {
+    let (src_w, src_h) = (frame.width as usize, frame.height as usize);
+    let mut out = vec![0u8; (width * height * 4) as usize];
+    if src_w == 0 || src_h == 0 {
+        return out;
+    }
+
+    let x_ratio = src_w as f32 / width as f32;
+    let y_ratio = src_h as f32 / height as f32;
+
+    for y in 0..height as usize {
+        let sy = ((y as f32 + 0.5) * y_ratio - 0.5).clamp(0.0, (src_h - 1) as f32);
+        let y0 = sy as usize;
+        let y1 = (y0 + 1).min(src_h - 1);
+        let fy = sy - y0 as f32;
+
+        for x in 0..width as usize {
+            let sx = ((x as f32 + 0.5) * x_ratio - 0.5).clamp(0.0, (src_w - 1) as f32);
+            let x0 = sx as usize;
+            let x1 = (x0 + 1).min(src_w - 1);
+            let fx = sx - x0 as f32;
+
+            for c in 0..4 {
+                let p = |x: usize, y: usize| frame.data[(y * src_w + x) * 4 + c] as f32;
+                let top = p(x0, y0) + (p(x1, y0) - p(x0, y0)) * fx;
+                let bottom = p(x0, y1) + (p(x1, y1) - p(x0, y1)) * fx;
+                out[(y * width as usize + x) * 4 + c] = (top + (bottom - top) * fy).round() as u8;
+            }
+        }
+    }
+    out
+}
@@ -1,83 +1,73 @@
 use godot::prelude::*;
-use godot::engine::{Node, AudioStreamGenerator, AudioStreamPlayer};
-use crate::audio_handler::AudioHandler;
+use godot::classes::{AudioStreamPlayer3D, INode3D, Node3D};
 
+use crate::audio_stream::LiveKitAudioStream;
+
+/// Plays one participant's voice through an `AudioStreamPlayer3D`.
+///
+/// Create it with `LiveKitManager.create_participant_audio(identity)` and add
+/// it under the participant's avatar so the voice follows the avatar around.
 #[derive(GodotClass)]
-#[class(base=Node)]
+#[class(base=Node3D)]
 pub struct ParticipantAudio {
-    base: Base<Node>,
-    
+    base: Base<Node3D>,
+
     #[var]
     participant_id: GString,
-    
-    #[var]
-    position: Vector3,
-    
-    audio_handler: AudioHandler,
-    audio_player: Option<Gd<AudioStreamPlayer>>,
+
+    stream: Option<Gd<LiveKitAudioStream>>,
+    audio_player: Option<Gd<AudioStreamPlayer3D>>,
 }
 
 #[godot_api]
-impl INode for ParticipantAudio {
-    fn init(base: Base<Node>) -> Self {
+impl INode3D for ParticipantAudio {
+    fn init(base: Base<Node3D>) -> Self {
         Self {
             base,
             participant_id: GString::from(""),
-            position: Vector3::ZERO,
-            audio_handler: AudioHandler::new(),
+            stream: None,
             audio_player: None,
         }
     }
 
     fn ready(&mut self) {
         godot_print!("ParticipantAudio ready for: {}", self.participant_id);
-        
-        // Create AudioStreamGenerator for real-time audio
-        let mut generator = AudioStreamGenerator::new_gd();
-        generator.set_mix_rate(48000.0); // 48kHz for LiveKit
-        generator.set_buffer_length(0.1); // 100ms buffer
-        
-        // Create audio player
-        let mut player = AudioStreamPlayer::new_gd();
-        player.set_stream(generator.upcast());
-        player.set_autoplay(true);
-        
-        self.base_mut().add_child(player.clone().upcast());
+
+        let Some(stream) = self.stream.clone() else {
+            godot_error!("ParticipantAudio: create it with LiveKitManager.create_participant_audio()");
+            return;
+        };
+
+        // Create the positional player; it inherits this node's transform
+        let mut player = AudioStreamPlayer3D::new_gd();
+        player.set_stream(&stream);
+
+        self.base_mut().add_child(&player);
         player.play();
-        
+
         self.audio_player = Some(player);
-        
-        godot_print!("Audio playback initialized for participant");
-    }
 
-    fn process(&mut self, _delta: f64) {
-        // Audio frame processing would happen here
-        // In complete implementation: receive from LiveKit, process, push to playback
+        godot_print!("Audio playback initialized for participant");
     }
 }
 
 #[godot_api]
 impl ParticipantAudio {
-    #[func]
-    pub fn set_participant_id(&mut self, id: GString) {
-        self.participant_id = id;
-    }
-
-    #[func]
-    pub fn get_participant_id(&self) -> GString {
-        self.participant_id.clone()
+    /// Connects this node to the decoded audio of its participant.
+    pub(crate) fn attach(&mut self, participant_id: GString, stream: Gd<LiveKitAudioStream>) {
+        self.participant_id = participant_id;
+        self.stream = Some(stream);
     }
 
+    /// Offsets the voice from the parent node, e.g. up to an avatar's head.
     #[func]
     pub fn set_spatial_position(&mut self, pos: Vector3) {
-        self.position = pos;
-        // In a full implementation, this would adjust 3D audio parameters
-        // For now, we're using basic AudioStreamPlayer
+        self.base_mut().set_position(pos);
     }
 
     #[func]
     pub fn get_spatial_position(&self) -> Vector3 {
-        self.position
+        self.base().get_position()
     }
 
     #[func]
@@ -86,4 +76,10 @@ impl ParticipantAudio {
             player.set_volume_db(db);
         }
     }
+
+    /// The underlying player, for tuning attenuation, max distance and bus.
+    #[func]
+    pub fn get_audio_player(&self) -> Option<Gd<AudioStreamPlayer3D>> {
+        self.audio_player.clone()
+    }
 }
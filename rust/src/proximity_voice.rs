@@ -0,0 +1,204 @@
+use godot::prelude::*;
+use godot::classes::{INode, Node};
+use std::collections::HashMap;
+
+use crate::livekit_client::LiveKitManager;
+
+/// Subscribes to the voices of participants near the listener and drops the
+/// rest, so large rooms only download and decode what can be heard.
+///
+/// Voices are picked up inside `subscribe_radius` and only dropped again
+/// outside `unsubscribe_radius`, so someone standing on the edge doesn't
+/// flap. At most `max_voices` are subscribed; current speakers win, then the
+/// nearest. Turn off `LiveKitManager.auto_subscribe` so voices out of range
+/// aren't received at all before the first update.
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct ProximityVoiceManager {
+    base: Base<Node>,
+
+    #[var]
+    manager: Option<Gd<LiveKitManager>>,
+    /// Node whose position is used as the listener, e.g. the local camera.
+    /// When unset, `set_listener_position` is used instead.
+    #[var]
+    listener: Option<Gd<Node3D>>,
+    #[var]
+    subscribe_radius: f32,
+    #[var]
+    unsubscribe_radius: f32,
+    /// 0 means no limit.
+    #[var]
+    max_voices: i32,
+    /// Seconds between re-evaluations.
+    #[var]
+    update_interval: f64,
+
+    listener_position: Vector3,
+    participants: HashMap<String, Gd<Node3D>>,
+    subscribed: HashMap<String, bool>, // Last state sent to the manager
+    since_update: f64,
+}
+
+#[godot_api]
+impl INode for ProximityVoiceManager {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            base,
+            manager: None,
+            listener: None,
+            subscribe_radius: 20.0,
+            unsubscribe_radius: 25.0,
+            max_voices: 16,
+            update_interval: 0.25,
+            listener_position: Vector3::ZERO,
+            participants: HashMap::new(),
+            subscribed: HashMap::new(),
+            since_update: 0.0,
+        }
+    }
+
+    fn process(&mut self, delta: f64) {
+        self.since_update += delta;
+        if self.since_update >= self.update_interval {
+            self.since_update = 0.0;
+            self.update_subscriptions();
+        }
+    }
+}
+
+#[godot_api]
+impl ProximityVoiceManager {
+    #[signal]
+    fn voice_subscription_changed(identity: GString, subscribed: bool);
+
+    /// Tracks `node` as the position of `identity`'s voice.
+    #[func]
+    pub fn register_participant(&mut self, identity: GString, node: Gd<Node3D>) {
+        self.participants.insert(identity.to_string(), node);
+    }
+
+    /// Stops tracking `identity`, dropping their voice if it was subscribed.
+    #[func]
+    pub fn unregister_participant(&mut self, identity: GString) {
+        let identity = identity.to_string();
+        self.participants.remove(&identity);
+        self.forget_untracked();
+    }
+
+    /// Replaces all tracked participants with an identity → `Node3D` map.
+    #[func]
+    pub fn set_participants(&mut self, participants: Dictionary) {
+        self.participants.clear();
+        for (identity, node) in participants.iter_shared() {
+            match node.try_to::<Gd<Node3D>>() {
+                Ok(node) => {
+                    self.participants.insert(identity.to_string(), node);
+                }
+                Err(_) => godot_warn!("ProximityVoiceManager: {} is not mapped to a Node3D", identity),
+            }
+        }
+        self.forget_untracked();
+    }
+
+    #[func]
+    pub fn set_listener_position(&mut self, position: Vector3) {
+        self.listener_position = position;
+    }
+
+    #[func]
+    pub fn is_voice_subscribed(&self, identity: GString) -> bool {
+        self.subscribed.get(&identity.to_string()).copied().unwrap_or(false)
+    }
+
+    /// Re-evaluates subscriptions now instead of waiting for the next interval.
+    #[func]
+    pub fn update_subscriptions(&mut self) {
+        let Some(mut manager) = self.manager.clone() else {
+            return;
+        };
+
+        let listener = match &self.listener {
+            Some(node) if node.is_instance_valid() => node.get_global_position(),
+            _ => self.listener_position,
+        };
+
+        // Participants whose avatar was freed are forgotten
+        self.participants.retain(|_, node| node.is_instance_valid());
+        self.forget_untracked();
+
+        let speaking = manager.bind().get_active_speakers();
+        let speaking: Vec<String> = speaking.as_slice().iter().map(GString::to_string).collect();
+
+        let mut candidates: Vec<(bool, f32, &String)> = self
+            .participants
+            .iter()
+            .filter_map(|(identity, node)| {
+                let distance = node.get_global_position().distance_to(listener);
+                let was_subscribed = self.subscribed.get(identity).copied().unwrap_or(false);
+                let radius = if was_subscribed {
+                    self.unsubscribe_radius.max(self.subscribe_radius)
+                } else {
+                    self.subscribe_radius
+                };
+                (distance <= radius).then(|| (!speaking.contains(identity), distance, identity))
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)));
+        if self.max_voices > 0 {
+            candidates.truncate(self.max_voices as usize);
+        }
+        let wanted: Vec<String> = candidates.into_iter().map(|(_, _, identity)| identity.clone()).collect();
+
+        let identities: Vec<String> = self.participants.keys().cloned().collect();
+        for identity in identities {
+            let subscribe = wanted.contains(&identity);
+            if self.subscribed.get(&identity) == Some(&subscribe) {
+                continue;
+            }
+
+            self.subscribed.insert(identity.clone(), subscribe);
+            self.send_subscription(&mut manager, &identity, subscribe);
+        }
+    }
+}
+
+impl ProximityVoiceManager {
+    /// Forgets the state of identities no longer tracked. Voices still
+    /// subscribed are dropped, since the manager remembers the override and
+    /// would otherwise keep decoding them.
+    fn forget_untracked(&mut self) {
+        let untracked: Vec<(String, bool)> = self
+            .subscribed
+            .iter()
+            .filter(|(identity, _)| !self.participants.contains_key(*identity))
+            .map(|(identity, &subscribed)| (identity.clone(), subscribed))
+            .collect();
+
+        for (identity, subscribed) in untracked {
+            self.subscribed.remove(&identity);
+            if !subscribed {
+                continue;
+            }
+            if let Some(mut manager) = self.manager.clone() {
+                self.send_subscription(&mut manager, &identity, false);
+            }
+        }
+    }
+
+    fn send_subscription(
+        &mut self,
+        manager: &mut Gd<LiveKitManager>,
+        identity: &str,
+        subscribe: bool,
+    ) {
+        manager
+            .bind_mut()
+            .set_participant_subscribed(GString::from(identity), subscribe);
+        self.base_mut().emit_signal(
+            "voice_subscription_changed",
+            &[identity.to_variant(), subscribe.to_variant()],
+        );
+    }
+}
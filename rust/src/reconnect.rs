@@ -0,0 +1,44 @@
+use rand::Rng;
+use std::time::Duration;
+
+/// Backoff settings used by `LiveKitManager` when a room connection drops.
+#[derive(Clone, Copy, Debug)]
+pub struct ReconnectPolicy {
+    pub enabled: bool,
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl ReconnectPolicy {
+    /// Delay before the given attempt (1-based): exponential backoff capped at
+    /// `max_delay_ms`, with "equal jitter" (a random delay between half the
+    /// cap and the cap) so a room full of clients that lost the same access
+    /// point don't all hammer the server at the same instant.
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let ceiling = self
+            .base_delay_ms
+            .saturating_mul(1u64 << exponent)
+            .min(self.max_delay_ms)
+            .max(1);
+
+        let jittered = rand::thread_rng().gen_range(ceiling / 2..=ceiling);
+        Duration::from_millis(jittered)
+    }
+
+    pub fn attempts_exhausted(&self, attempt: u32) -> bool {
+        attempt > self.max_attempts
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_attempts: 10,
+            base_delay_ms: 500,
+            max_delay_ms: 15000,
+        }
+    }
+}
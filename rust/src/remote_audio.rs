@@ -0,0 +1,153 @@
+use godot::prelude::*;
+use godot::classes::AudioStreamGeneratorPlayback;
+use godot::classes::native::AudioFrame as GodotAudioFrame;
+use ringbuf::traits::{Producer, Split};
+use ringbuf::{HeapProd, HeapRb};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::jitter_buffer::{AudioStats, JitterBuffer};
+use crate::resampler::{Resampler, ResamplerQuality};
+
+/// Sample rate remote tracks are decoded at.
+pub const REMOTE_SAMPLE_RATE: u32 = 48000;
+/// Channels remote tracks are decoded to. Mono tracks arrive with both
+/// channels equal.
+pub const REMOTE_CHANNELS: u32 = 2;
+
+/// Interleaved left/right sample pair.
+pub type StereoFrame = [f32; 2];
+
+/// Creates a connected sink/receiver pair holding at most `max_latency_ms` of
+/// audio at `output_rate`, the rate the reader plays at. Decoded audio is
+/// resampled from `REMOTE_SAMPLE_RATE` on the way in. The jitter buffer adapts
+/// its depth up to half of the buffer.
+pub fn audio_channel(
+    max_latency_ms: u32,
+    output_rate: u32,
+    quality: ResamplerQuality,
+) -> (Arc<RemoteAudioSink>, RemoteAudioReceiver) {
+    let capacity = (output_rate * max_latency_ms / 1000) as usize;
+    let (producer, consumer) = HeapRb::<StereoFrame>::new(capacity).split();
+    let stats = Arc::new(AudioStats::default());
+    (
+        Arc::new(RemoteAudioSink {
+            writer: Mutex::new(SinkWriter {
+                producer,
+                resampler: Resampler::new(REMOTE_SAMPLE_RATE, output_rate, 2, quality),
+                scratch: Vec::new(),
+                resampled: Vec::new(),
+            }),
+            stats: stats.clone(),
+        }),
+        RemoteAudioReceiver {
+            jitter: JitterBuffer::new(consumer, stats, output_rate),
+        },
+    )
+}
+
+/// Write half of a participant's audio ring buffer, fed by the
+/// `NativeAudioStream` task. The mutex only serialises producers; the reading
+/// side never takes it.
+pub struct RemoteAudioSink {
+    writer: Mutex<SinkWriter>,
+    stats: Arc<AudioStats>,
+}
+
+struct SinkWriter {
+    producer: HeapProd<StereoFrame>,
+    resampler: Resampler,
+    // Reused between frames to avoid allocating on every 10ms chunk
+    scratch: Vec<f32>,
+    resampled: Vec<f32>,
+}
+
+impl RemoteAudioSink {
+    /// Appends interleaved samples with `channels` channels decoded at
+    /// `REMOTE_SAMPLE_RATE`. Mono is played on both sides and channels past
+    /// the second are ignored. If the reader has stalled and the buffer is
+    /// full the newest audio is dropped.
+    pub fn push_interleaved(&self, samples: &[i16], channels: usize) {
+        let mut writer = self.writer.lock().unwrap();
+        let SinkWriter { producer, resampler, scratch, resampled } = &mut *writer;
+
+        let to_f32 = |sample: i16| (sample as f32) / 32768.0;
+        scratch.clear();
+        for frame in samples.chunks_exact(channels.max(1)) {
+            let left = to_f32(frame[0]);
+            let right = frame.get(1).copied().map_or(left, to_f32);
+            scratch.extend_from_slice(&[left, right]);
+        }
+        resampled.clear();
+        resampler.process(scratch, resampled);
+
+        let frames = resampled.len() / 2;
+        let pushed = producer.push_iter(resampled.chunks_exact(2).map(|f| [f[0], f[1]]));
+        self.stats.record_dropped(frames - pushed);
+    }
+
+    pub fn stats(&self) -> &AudioStats {
+        &self.stats
+    }
+}
+
+/// Read half of a participant's audio ring buffer, owned by whatever plays it.
+pub struct RemoteAudioReceiver {
+    jitter: JitterBuffer,
+}
+
+impl RemoteAudioReceiver {
+    /// Pushes up to `max_frames` into a generator, limited by the room it has.
+    /// Callers pass the frames worth of time elapsed (plus some slack) so the
+    /// jitter buffer is read at playback pace rather than as fast as
+    /// the generator will take it.
+    pub fn drain_into(&mut self, playback: &mut Gd<AudioStreamGeneratorPlayback>, max_frames: usize) {
+        let count = max_frames.min(playback.get_frames_available().max(0) as usize);
+        if count == 0 {
+            return;
+        }
+
+        let mut buffer = PackedVector2Array::new();
+        buffer.resize(count);
+        let frames = buffer.as_mut_slice();
+        let mut index = 0;
+        self.jitter.read(count, |[left, right]| {
+            frames[index] = Vector2::new(left, right);
+            index += 1;
+        });
+        playback.push_buffer(&buffer);
+    }
+
+    /// Fills `out` from the jitter buffer on the audio thread.
+    pub fn mix(&mut self, out: &mut [GodotAudioFrame]) {
+        let mut frames = out.iter_mut();
+        self.jitter.read(frames.len(), |[left, right]| {
+            if let Some(dst) = frames.next() {
+                dst.left = left;
+                dst.right = right;
+            }
+        });
+    }
+}
+
+/// Sinks registered per participant identity and track name; an empty name
+/// is the participant's microphone. Entries are weak so a sink goes away with
+/// the player that owns it.
+#[derive(Clone, Default)]
+pub struct RemoteAudioSinks(Arc<Mutex<HashMap<(String, String), Weak<RemoteAudioSink>>>>);
+
+impl RemoteAudioSinks {
+    pub fn register(&self, identity: &str, track_name: &str, sink: &Arc<RemoteAudioSink>) {
+        let mut sinks = self.0.lock().unwrap();
+        sinks.retain(|_, s| s.strong_count() > 0);
+        sinks.insert((identity.to_string(), track_name.to_string()), Arc::downgrade(sink));
+    }
+
+    pub fn get(&self, identity: &str, track_name: &str) -> Option<Arc<RemoteAudioSink>> {
+        self.0
+            .lock()
+            .unwrap()
+            .get(&(identity.to_string(), track_name.to_string()))
+            .and_then(Weak::upgrade)
+    }
+}
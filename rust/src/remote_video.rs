@@ -0,0 +1,163 @@
+use livekit::publication::RemoteTrackPublication;
+use livekit::track::{self, TrackDimension};
+use livekit::webrtc::{native::yuv_helper, video_frame::BoxVideoFrame};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+
+/// A decoded video frame converted to tightly packed RGBA8.
+pub struct RgbaFrame {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl RgbaFrame {
+    /// Converts whatever buffer the decoder produced to I420, then to RGBA.
+    pub fn from_video_frame(frame: &BoxVideoFrame) -> Self {
+        let i420 = frame.buffer.to_i420();
+        let (width, height) = (i420.width(), i420.height());
+        let (stride_y, stride_u, stride_v) = i420.strides();
+        let (y, u, v) = i420.data();
+
+        // RGBA bytes, see the libyuv naming note in local_video.rs
+        let mut data = vec![0u8; (width * height * 4) as usize];
+        yuv_helper::i420_to_abgr(
+            y,
+            stride_y,
+            u,
+            stride_u,
+            v,
+            stride_v,
+            &mut data,
+            width * 4,
+            width as i32,
+            height as i32,
+        );
+
+        Self { width, height, data }
+    }
+}
+
+/// Newest frame waiting for one texture. Frames the texture didn't pick up
+/// in time are replaced, never queued.
+#[derive(Default)]
+pub struct VideoFrameSlot(Mutex<Option<Arc<RgbaFrame>>>);
+
+impl VideoFrameSlot {
+    pub fn store(&self, frame: Arc<RgbaFrame>) {
+        *self.0.lock().unwrap() = Some(frame);
+    }
+
+    pub fn take(&self) -> Option<Arc<RgbaFrame>> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+struct VideoSink {
+    identity: String,
+    track_name: String, // Empty matches every video track of the participant
+    slot: Weak<VideoFrameSlot>,
+}
+
+/// Texture slots per participant, filled by the video decoding tasks.
+/// Entries are dropped once their texture is freed.
+#[derive(Clone, Default)]
+pub struct RemoteVideoSinks(Arc<Mutex<Vec<VideoSink>>>);
+
+impl RemoteVideoSinks {
+    pub fn register(&self, identity: &str, track_name: &str, slot: &Arc<VideoFrameSlot>) {
+        self.0.lock().unwrap().push(VideoSink {
+            identity: identity.to_string(),
+            track_name: track_name.to_string(),
+            slot: Arc::downgrade(slot),
+        });
+    }
+
+    /// Slots that want frames from `identity`'s track called `track_name`.
+    pub fn slots_for(&self, identity: &str, track_name: &str) -> Vec<Arc<VideoFrameSlot>> {
+        let mut sinks = self.0.lock().unwrap();
+        sinks.retain(|sink| sink.slot.strong_count() > 0);
+        sinks
+            .iter()
+            .filter(|sink| {
+                sink.identity == identity
+                    && (sink.track_name.is_empty() || sink.track_name == track_name)
+            })
+            .filter_map(|sink| sink.slot.upgrade())
+            .collect()
+    }
+}
+
+/// Simulcast layer a subscriber asks for. The discriminants are the values of
+/// the `VIDEO_QUALITY_*` constants exposed to GDScript.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VideoQuality {
+    Low = 0,
+    Medium = 1,
+    High = 2,
+}
+
+impl VideoQuality {
+    pub fn from_i32(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Low),
+            1 => Some(Self::Medium),
+            2 => Some(Self::High),
+            _ => None,
+        }
+    }
+}
+
+impl From<VideoQuality> for track::VideoQuality {
+    fn from(quality: VideoQuality) -> Self {
+        match quality {
+            VideoQuality::Low => Self::Low,
+            VideoQuality::Medium => Self::Medium,
+            VideoQuality::High => Self::High,
+        }
+    }
+}
+
+/// What a subscriber asked for on a remote video track; the last call wins.
+#[derive(Clone, Copy, Debug)]
+pub enum VideoPreference {
+    Quality(VideoQuality),
+    Dimensions(u32, u32),
+}
+
+/// `set_video_quality` and `set_video_dimensions` requests per (identity,
+/// track name), with an empty name for all of the participant's tracks.
+/// Remembered so they also apply to tracks subscribed later and after
+/// reconnects.
+#[derive(Clone, Default)]
+pub struct VideoPreferences(Arc<Mutex<HashMap<(String, String), VideoPreference>>>);
+
+impl VideoPreferences {
+    pub fn set(&self, identity: &str, track_name: &str, preference: VideoPreference) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert((identity.to_string(), track_name.to_string()), preference);
+    }
+
+    /// Sends the preference for `identity`'s `publication`, if there is one.
+    /// Must run inside the runtime, since the SDK sends it from a task.
+    pub fn apply(&self, identity: &str, publication: &RemoteTrackPublication) {
+        let preference = {
+            let wanted = self.0.lock().unwrap();
+            let exact = (identity.to_string(), publication.name());
+            let any = (identity.to_string(), String::new());
+            match wanted.get(&exact).or_else(|| wanted.get(&any)) {
+                Some(&preference) => preference,
+                None => return,
+            }
+        };
+
+        match preference {
+            VideoPreference::Quality(quality) => publication.set_video_quality(quality.into()),
+            VideoPreference::Dimensions(width, height) => {
+                publication.update_video_dimensions(TrackDimension(width, height))
+            }
+        }
+    }
+}
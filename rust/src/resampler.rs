@@ -0,0 +1,340 @@
+use std::f64::consts::PI;
+
+/// Interpolation used by `Resampler`. The discriminants are the values of the
+/// `RESAMPLER_*` constants exposed to GDScript.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResamplerQuality {
+    /// Two-point linear interpolation. Cheapest, audibly dull on speech.
+    Linear = 0,
+    /// Four-point Catmull-Rom spline. Good default for voice.
+    Cubic = 1,
+    /// 16-tap Blackman-windowed sinc with anti-aliasing when downsampling.
+    Sinc = 2,
+}
+
+impl ResamplerQuality {
+    pub fn from_i32(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(Self::Linear),
+            1 => Some(Self::Cubic),
+            2 => Some(Self::Sinc),
+            _ => None,
+        }
+    }
+
+    /// Input frames needed on each side of the interpolation point.
+    fn half_width(self) -> usize {
+        match self {
+            Self::Linear => 1,
+            Self::Cubic => 2,
+            Self::Sinc => SINC_HALF_WIDTH,
+        }
+    }
+}
+
+const SINC_HALF_WIDTH: usize = 8;
+const SINC_PHASES: usize = 256;
+
+/// Streaming sample-rate converter for interleaved f32 audio.
+///
+/// Keeps the tail of the previous call so consecutive chunks (e.g. 10ms
+/// frames) resample without clicks at their boundaries.
+pub struct Resampler {
+    channels: usize,
+    quality: ResamplerQuality,
+    in_rate: u32,
+    out_rate: u32,
+    /// Read position in `buffer`, in 1/`out_rate` frames so it stays exact
+    /// however the input is chunked. Each output frame advances it `in_rate`.
+    pos: u64,
+    /// Pending input, interleaved, starting `half_width - 1` frames before `pos`.
+    buffer: Vec<f32>,
+    /// Sinc kernel sampled at `SINC_PHASES` fractional offsets.
+    sinc_table: Vec<f32>,
+}
+
+impl Resampler {
+    pub fn new(in_rate: u32, out_rate: u32, channels: usize, quality: ResamplerQuality) -> Self {
+        let half = quality.half_width();
+        let sinc_table = if quality == ResamplerQuality::Sinc {
+            build_sinc_table(in_rate, out_rate)
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            channels,
+            quality,
+            in_rate,
+            out_rate,
+            pos: ((half - 1) * out_rate as usize) as u64,
+            // Lead-in silence so the first input frame can be interpolated
+            buffer: vec![0.0; (half - 1) * channels],
+            sinc_table,
+        }
+    }
+
+    /// Whether input passes through unchanged.
+    pub fn is_passthrough(&self) -> bool {
+        self.in_rate == self.out_rate
+    }
+
+    /// Resamples `input` and appends the result to `output`.
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        if self.is_passthrough() {
+            output.extend_from_slice(input);
+            return;
+        }
+
+        let channels = self.channels;
+        let half = self.quality.half_width();
+        self.buffer.extend_from_slice(input);
+        let frames = self.buffer.len() / channels;
+        let (in_rate, out_rate) = (self.in_rate as u64, self.out_rate as u64);
+
+        output.reserve(
+            (input.len() / channels) * out_rate as usize / in_rate as usize * channels + channels,
+        );
+        while (self.pos / out_rate) as usize + half < frames {
+            let index = (self.pos / out_rate) as usize;
+            let frac = (self.pos % out_rate) as f64 / out_rate as f64;
+            for channel in 0..channels {
+                output.push(self.interpolate(index, frac, channel));
+            }
+            self.pos += in_rate;
+        }
+
+        // Drop input that no future output frame can reach
+        let consumed = ((self.pos / out_rate) as usize)
+            .saturating_sub(half - 1)
+            .min(frames);
+        self.buffer.drain(..consumed * channels);
+        self.pos -= consumed as u64 * out_rate;
+    }
+
+    fn sample(&self, frame: usize, channel: usize) -> f32 {
+        self.buffer[frame * self.channels + channel]
+    }
+
+    fn interpolate(&self, index: usize, frac: f64, channel: usize) -> f32 {
+        let t = frac as f32;
+        match self.quality {
+            ResamplerQuality::Linear => {
+                let a = self.sample(index, channel);
+                let b = self.sample(index + 1, channel);
+                a + (b - a) * t
+            }
+            ResamplerQuality::Cubic => {
+                let p0 = self.sample(index - 1, channel);
+                let p1 = self.sample(index, channel);
+                let p2 = self.sample(index + 1, channel);
+                let p3 = self.sample(index + 2, channel);
+                let a = -0.5 * p0 + 1.5 * p1 - 1.5 * p2 + 0.5 * p3;
+                let b = p0 - 2.5 * p1 + 2.0 * p2 - 0.5 * p3;
+                let c = -0.5 * p0 + 0.5 * p2;
+                ((a * t + b) * t + c) * t + p1
+            }
+            ResamplerQuality::Sinc => {
+                let taps = 2 * SINC_HALF_WIDTH;
+                let phase = ((frac * SINC_PHASES as f64) as usize).min(SINC_PHASES - 1);
+                let kernel = &self.sinc_table[phase * taps..(phase + 1) * taps];
+                let first = index + 1 - SINC_HALF_WIDTH;
+                kernel
+                    .iter()
+                    .enumerate()
+                    .map(|(tap, weight)| weight * self.sample(first + tap, channel))
+                    .sum()
+            }
+        }
+    }
+}
+
+/// Precomputes normalised windowed-sinc weights for each fractional phase.
+/// The cutoff drops below Nyquist when downsampling to avoid aliasing.
+fn build_sinc_table(in_rate: u32, out_rate: u32) -> Vec<f32> {
+    let taps = 2 * SINC_HALF_WIDTH;
+    let cutoff = (out_rate as f64 / in_rate as f64).min(1.0);
+    let mut table = Vec::with_capacity(SINC_PHASES * taps);
+
+    for phase in 0..SINC_PHASES {
+        let frac = phase as f64 / SINC_PHASES as f64;
+        let weights: Vec<f64> = (0..taps)
+            .map(|tap| {
+                // Distance from the interpolation point to this input frame
+                let x = tap as f64 - (SINC_HALF_WIDTH as f64 - 1.0) - frac;
+                let sinc = if x.abs() < 1e-9 {
+                    1.0
+                } else {
+                    (PI * cutoff * x).sin() / (PI * cutoff * x)
+                };
+                let n = (x + SINC_HALF_WIDTH as f64) / (2.0 * SINC_HALF_WIDTH as f64);
+                let window = 0.42 - 0.5 * (2.0 * PI * n).cos() + 0.08 * (4.0 * PI * n).cos();
+                sinc * window
+            })
+            .collect();
+
+        // Unity gain at DC regardless of phase
+        let sum: f64 = weights.iter().sum();
+        table.extend(weights.iter().map(|w| (w / sum) as f32));
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUALITIES: [ResamplerQuality; 3] = [
+        ResamplerQuality::Linear,
+        ResamplerQuality::Cubic,
+        ResamplerQuality::Sinc,
+    ];
+    const AMPLITUDE: f64 = 0.5;
+    const TONE_HZ: f64 = 997.0;
+
+    fn sine(rate: u32, freq: f64, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|i| (AMPLITUDE * (2.0 * PI * freq * i as f64 / rate as f64).sin()) as f32)
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f64 {
+        let sum: f64 = samples.iter().map(|&s| (s as f64) * (s as f64)).sum();
+        (sum / samples.len() as f64).sqrt()
+    }
+
+    fn resample(in_rate: u32, out_rate: u32, quality: ResamplerQuality, input: &[f32]) -> Vec<f32> {
+        let mut output = Vec::new();
+        Resampler::new(in_rate, out_rate, 1, quality).process(input, &mut output);
+        output
+    }
+
+    /// Output frames away from the start and the held-back tail.
+    fn steady(output: &[f32]) -> &[f32] {
+        &output[64..output.len() - 64]
+    }
+
+    #[test]
+    fn output_length_follows_rate_ratio() {
+        for quality in QUALITIES {
+            for (in_rate, out_rate) in [(44100, 48000), (48000, 44100)] {
+                let output = resample(
+                    in_rate,
+                    out_rate,
+                    quality,
+                    &sine(in_rate, TONE_HZ, in_rate as usize),
+                );
+
+                // A second of input gives a second of output, less the frames held
+                // back until the next call can interpolate them
+                let expected = out_rate as usize;
+                assert!(
+                    output.len() <= expected,
+                    "{quality:?} {in_rate}->{out_rate}"
+                );
+                assert!(
+                    expected - output.len() <= quality.half_width() + 1,
+                    "{quality:?} {in_rate}->{out_rate}: {} frames",
+                    output.len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn frequency_is_preserved() {
+        for quality in QUALITIES {
+            for (in_rate, out_rate) in [(44100, 48000), (48000, 44100)] {
+                let output = resample(
+                    in_rate,
+                    out_rate,
+                    quality,
+                    &sine(in_rate, TONE_HZ, in_rate as usize),
+                );
+                let samples = steady(&output);
+
+                let rising = samples
+                    .windows(2)
+                    .filter(|w| w[0] < 0.0 && w[1] >= 0.0)
+                    .count();
+                let seconds = samples.len() as f64 / out_rate as f64;
+                let freq = rising as f64 / seconds;
+                assert!(
+                    (freq - TONE_HZ).abs() < TONE_HZ * 0.01,
+                    "{quality:?} {in_rate}->{out_rate}: {freq} Hz"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn amplitude_is_preserved() {
+        for quality in QUALITIES {
+            for (in_rate, out_rate) in [(44100, 48000), (48000, 44100)] {
+                let output = resample(
+                    in_rate,
+                    out_rate,
+                    quality,
+                    &sine(in_rate, TONE_HZ, in_rate as usize),
+                );
+
+                // Every kernel is zero phase, so the output lines up with the ideal tone
+                let ideal = sine(out_rate, TONE_HZ, output.len());
+                let error: Vec<f32> = output.iter().zip(&ideal).map(|(a, b)| a - b).collect();
+                let ratio = rms(steady(&output)) / (AMPLITUDE / 2f64.sqrt());
+
+                assert!(
+                    (ratio - 1.0).abs() < 0.01,
+                    "{quality:?} {in_rate}->{out_rate}: gain {ratio}"
+                );
+                assert!(
+                    rms(steady(&error)) < 0.002,
+                    "{quality:?} {in_rate}->{out_rate}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn chunked_matches_one_shot() {
+        for quality in QUALITIES {
+            for (in_rate, out_rate) in [(44100, 48000), (48000, 44100)] {
+                let frames = in_rate as usize;
+                let left = sine(in_rate, TONE_HZ, frames);
+                let right = sine(in_rate, TONE_HZ * 3.0, frames);
+                let input: Vec<f32> = left
+                    .iter()
+                    .zip(&right)
+                    .flat_map(|(&l, &r)| [l, r])
+                    .collect();
+
+                let mut one_shot = Vec::new();
+                Resampler::new(in_rate, out_rate, 2, quality).process(&input, &mut one_shot);
+
+                // 10ms chunks, as delivered by the mic and the decoder
+                let mut chunked = Vec::new();
+                let mut resampler = Resampler::new(in_rate, out_rate, 2, quality);
+                for chunk in input.chunks(in_rate as usize / 100 * 2) {
+                    resampler.process(chunk, &mut chunked);
+                }
+
+                assert_eq!(chunked, one_shot, "{quality:?} {in_rate}->{out_rate}");
+            }
+        }
+    }
+
+    #[test]
+    fn sinc_filters_tones_above_output_nyquist() {
+        let (in_rate, out_rate) = (48000, 16000);
+        let input = sine(in_rate, 12000.0, in_rate as usize);
+
+        let sinc = resample(in_rate, out_rate, ResamplerQuality::Sinc, &input);
+        let linear = resample(in_rate, out_rate, ResamplerQuality::Linear, &input);
+
+        // Linear lets the tone alias back in; sinc keeps it at least 20 dB down
+        let input_rms = rms(&input);
+        assert!(rms(steady(&linear)) > input_rms * 0.5);
+        assert!(rms(steady(&sinc)) < input_rms * 0.1);
+    }
+}
@@ -0,0 +1,116 @@
+use godot::prelude::*;
+use livekit::options::{self, TrackPublishOptions, VideoCodec, VideoEncoding};
+use livekit::track::TrackSource;
+use livekit::webrtc::video_source::{native::NativeVideoSource, VideoResolution};
+
+/// Size, rate and encoding a published video track is created with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VideoTrackOptions {
+    pub width: u32,
+    pub height: u32,
+    pub fps: f64,
+    /// Also send lower resolution layers so subscribers can pick one.
+    pub simulcast: bool,
+    pub codec: VideoCodec,
+    /// 0 picks LiveKit's preset for the resolution and codec.
+    pub max_bitrate: u32,
+}
+
+impl VideoTrackOptions {
+    /// I420 needs even dimensions, so odd ones are rounded down.
+    pub fn new(width: u32, height: u32, fps: f64) -> Self {
+        Self {
+            width: (width & !1).max(2),
+            height: (height & !1).max(2),
+            fps: fps.max(1.0),
+            simulcast: true,
+            codec: VideoCodec::VP8,
+            max_bitrate: 0,
+        }
+    }
+
+    pub fn create_source(&self) -> NativeVideoSource {
+        NativeVideoSource::new(VideoResolution { width: self.width, height: self.height })
+    }
+
+    /// Simulcast layers below the top one are derived from it by the SDK.
+    pub fn publish_options(&self) -> TrackPublishOptions {
+        let max_bitrate = match self.max_bitrate {
+            0 => {
+                options::compute_appropriate_encoding(false, self.width, self.height, self.codec)
+                    .max_bitrate
+            }
+            bitrate => bitrate as u64,
+        };
+
+        TrackPublishOptions {
+            video_encoding: Some(VideoEncoding { max_bitrate, max_framerate: self.fps }),
+            video_codec: self.codec,
+            simulcast: self.simulcast,
+            source: TrackSource::Camera,
+            ..Default::default()
+        }
+    }
+}
+
+/// Encoding for a published video track.
+///
+/// Pass it to `LiveKitManager.publish_viewport()` or
+/// `LiveKitManager.publish_video_source()`. Keep simulcast on when viewers
+/// may show the track small, so they can receive a lower layer.
+#[derive(GodotClass)]
+#[class(base=Resource)]
+pub struct LiveKitVideoOptions {
+    base: Base<Resource>,
+
+    /// Send low and medium layers alongside the full resolution.
+    #[export]
+    simulcast: bool,
+    #[export(enum = (VP8, H264, VP9, AV1))]
+    codec: i32,
+    /// Bitrate cap of the top layer in bits per second; 0 picks one for the
+    /// resolution and codec.
+    #[export]
+    max_bitrate: i32,
+}
+
+#[godot_api]
+impl IResource for LiveKitVideoOptions {
+    fn init(base: Base<Resource>) -> Self {
+        Self {
+            base,
+            simulcast: true,
+            codec: 0,
+            max_bitrate: 0,
+        }
+    }
+}
+
+impl LiveKitVideoOptions {
+    pub(crate) fn track_options(&self, width: u32, height: u32, fps: f64) -> VideoTrackOptions {
+        VideoTrackOptions {
+            simulcast: self.simulcast,
+            codec: match self.codec {
+                1 => VideoCodec::H264,
+                2 => VideoCodec::VP9,
+                3 => VideoCodec::AV1,
+                _ => VideoCodec::VP8,
+            },
+            max_bitrate: self.max_bitrate.max(0) as u32,
+            ..VideoTrackOptions::new(width, height, fps)
+        }
+    }
+
+    /// Track options for an optional resource; `None` uses the defaults.
+    pub(crate) fn resolve(
+        options: Option<Gd<Self>>,
+        width: u32,
+        height: u32,
+        fps: f64,
+    ) -> VideoTrackOptions {
+        options.map_or_else(
+            || VideoTrackOptions::new(width, height, fps),
+            |options| options.bind().track_options(width, height, fps),
+        )
+    }
+}
@@ -0,0 +1,111 @@
+use godot::prelude::*;
+use godot::classes::image::Format;
+use godot::classes::Image;
+use tokio::sync::mpsc;
+
+use crate::local_video::VideoInput;
+use crate::remote_video::RgbaFrame;
+use crate::video_options::{LiveKitVideoOptions, VideoTrackOptions};
+
+/// A video track fed with `Image`s pushed from GDScript on the caller's own
+/// schedule, e.g. a replay exporter. Set the size and rate, publish it with
+/// `LiveKitManager.publish_video_source(source, track_name, options)`, then call
+/// `push_image` for every frame. Images of any format and size are converted
+/// and scaled to the published size.
+#[derive(GodotClass)]
+#[class(base=RefCounted)]
+pub struct LiveKitVideoSource {
+    base: Base<RefCounted>,
+
+    /// Published size; odd values are rounded down. Changes apply the next
+    /// time the source is published.
+    #[var]
+    width: i32,
+    #[var]
+    height: i32,
+    /// Expected frame rate, used as the encoder's limit.
+    #[var]
+    fps: i32,
+
+    sender: Option<mpsc::UnboundedSender<VideoInput>>, // Set while published
+}
+
+#[godot_api]
+impl IRefCounted for LiveKitVideoSource {
+    fn init(base: Base<RefCounted>) -> Self {
+        Self {
+            base,
+            width: 1280,
+            height: 720,
+            fps: 30,
+            sender: None,
+        }
+    }
+}
+
+#[godot_api]
+impl LiveKitVideoSource {
+    /// Queues `image` to be sent. `timestamp_us` is its capture time in
+    /// microseconds; pass 0 to use the time it was pushed. Returns false if
+    /// the source isn't published.
+    #[func]
+    pub fn push_image(&self, image: Gd<Image>, timestamp_us: i64) -> bool {
+        let Some(sender) = &self.sender else {
+            return false;
+        };
+        let Some(frame) = image_to_rgba(&image) else {
+            return false;
+        };
+
+        let timestamp_us = (timestamp_us > 0).then_some(timestamp_us);
+        sender.send(VideoInput { frame, timestamp_us }).is_ok()
+    }
+
+    #[func]
+    pub fn is_published(&self) -> bool {
+        self.sender.is_some()
+    }
+}
+
+impl LiveKitVideoSource {
+    pub(crate) fn track_options(
+        &self,
+        options: Option<Gd<LiveKitVideoOptions>>,
+    ) -> VideoTrackOptions {
+        LiveKitVideoOptions::resolve(
+            options,
+            self.width.max(0) as u32,
+            self.height.max(0) as u32,
+            self.fps as f64,
+        )
+    }
+
+    /// Routes pushed images to a feeder task; `None` stops sending.
+    pub(crate) fn set_sender(&mut self, sender: Option<mpsc::UnboundedSender<VideoInput>>) {
+        self.sender = sender;
+    }
+}
+
+/// Copies `image` out as RGBA8, converting a copy if it has another format.
+pub(crate) fn image_to_rgba(image: &Gd<Image>) -> Option<RgbaFrame> {
+    if image.is_empty() {
+        return None;
+    }
+
+    let data = if image.get_format() == Format::RGBA8 {
+        image.get_data()
+    } else {
+        let mut converted = image.duplicate()?.cast::<Image>();
+        if converted.is_compressed() {
+            converted.decompress();
+        }
+        converted.convert(Format::RGBA8);
+        converted.get_data()
+    };
+
+    Some(RgbaFrame {
+        width: image.get_width() as u32,
+        height: image.get_height() as u32,
+        data: data.to_vec(),
+    })
+}
@@ -0,0 +1,87 @@
+use godot::prelude::*;
+use godot::classes::image::Format;
+use godot::classes::{Image, ImageTexture};
+use std::sync::Arc;
+
+use crate::remote_video::{RemoteVideoSinks, VideoFrameSlot};
+
+/// An `ImageTexture` showing a participant's video track, e.g. a webcam on
+/// an in-world screen. Frames are decoded and converted to RGBA off the main
+/// thread; the texture uploads the newest one once per frame.
+///
+/// Create it with `LiveKitManager.create_video_texture(identity, track_name)`.
+#[derive(GodotClass)]
+#[class(base=ImageTexture, init)]
+pub struct LiveKitVideoTexture {
+    base: Base<ImageTexture>,
+
+    #[var]
+    participant_id: GString,
+    #[var]
+    track_name: GString,
+
+    slot: Arc<VideoFrameSlot>,
+    frame_size: (u32, u32), // (0, 0) until the first frame arrives
+}
+
+#[godot_api]
+impl LiveKitVideoTexture {
+    /// The first frame arrived or the sender changed resolution.
+    #[signal]
+    fn frame_size_changed(size: Vector2i);
+
+    #[func]
+    pub fn has_frame(&self) -> bool {
+        self.frame_size != (0, 0)
+    }
+
+    /// Uploads the newest decoded frame, returning false if none arrived
+    /// since the last call. `LiveKitManager` calls this every frame.
+    #[func]
+    pub fn update_frame(&mut self) -> bool {
+        let Some(frame) = self.slot.take() else {
+            return false;
+        };
+
+        let data = PackedByteArray::from(frame.data.as_slice());
+        let Some(image) = Image::create_from_data(
+            frame.width as i32,
+            frame.height as i32,
+            false,
+            Format::RGBA8,
+            &data,
+        ) else {
+            return false;
+        };
+
+        // update() is cheaper but only works while the size stays the same
+        if self.frame_size == (frame.width, frame.height) {
+            self.base_mut().update(&image);
+        } else {
+            self.frame_size = (frame.width, frame.height);
+            self.base_mut().set_image(&image);
+            let size = Vector2i::new(frame.width as i32, frame.height as i32);
+            self.base_mut().emit_signal("frame_size_changed", &[size.to_variant()]);
+        }
+        true
+    }
+}
+
+impl LiveKitVideoTexture {
+    pub(crate) fn new_for(
+        participant_id: GString,
+        track_name: GString,
+        sinks: &RemoteVideoSinks,
+    ) -> Gd<Self> {
+        let slot = Arc::new(VideoFrameSlot::default());
+        sinks.register(&participant_id.to_string(), &track_name.to_string(), &slot);
+
+        Gd::from_init_fn(|base| Self {
+            base,
+            participant_id,
+            track_name,
+            slot,
+            frame_size: (0, 0),
+        })
+    }
+}
@@ -0,0 +1,142 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// What opens the microphone gate. The discriminants are the values of the
+/// `VOICE_*` constants exposed to GDScript.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoiceActivationMode {
+    /// Everything captured is sent.
+    OpenMic = 0,
+    /// Audio is sent while the voice activity detector hears speech.
+    VoiceActivity = 1,
+    /// Audio is sent while the push-to-talk action is held, plus a short tail.
+    PushToTalk = 2,
+}
+
+impl VoiceActivationMode {
+    pub fn from_i32(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(Self::OpenMic),
+            1 => Some(Self::VoiceActivity),
+            2 => Some(Self::PushToTalk),
+            _ => None,
+        }
+    }
+}
+
+/// Energy detector thresholds.
+#[derive(Clone, Copy, Debug)]
+pub struct VadConfig {
+    /// Frame RMS level, in dBFS, counted as speech.
+    pub threshold_db: f32,
+    /// Speech needed before the detector reports speaking.
+    pub attack_ms: u32,
+    /// Hangover after the last speech frame, so word endings aren't clipped.
+    pub release_ms: u32,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            threshold_db: -40.0,
+            attack_ms: 20,
+            release_ms: 300,
+        }
+    }
+}
+
+/// Mic gate configuration, changed from the main thread and read by the
+/// feeder task for every 10ms frame.
+#[derive(Clone, Copy, Debug)]
+pub struct MicGateSettings {
+    pub mode: VoiceActivationMode,
+    pub vad: VadConfig,
+    /// Send silence while the gate is closed instead of sending nothing.
+    pub send_silence: bool,
+    /// Push-to-talk key held or still within its release tail.
+    pub talk_key_open: bool,
+}
+
+impl MicGateSettings {
+    /// Push-to-talk mutes the published track while the key is up so others
+    /// see the mute indicator. Voice activity leaves the track unmuted.
+    pub fn mutes_track(&self) -> bool {
+        self.mode == VoiceActivationMode::PushToTalk && !self.talk_key_open
+    }
+}
+
+impl Default for MicGateSettings {
+    fn default() -> Self {
+        Self {
+            mode: VoiceActivationMode::OpenMic,
+            vad: VadConfig::default(),
+            send_silence: false,
+            talk_key_open: false,
+        }
+    }
+}
+
+/// Gate settings plus the detector's latest verdict, shared between the node
+/// and the feeder task.
+#[derive(Clone, Default)]
+pub struct SharedMicGate {
+    settings: Arc<Mutex<MicGateSettings>>,
+    speaking: Arc<AtomicBool>,
+}
+
+impl SharedMicGate {
+    pub fn settings(&self) -> MicGateSettings {
+        *self.settings.lock().unwrap()
+    }
+
+    pub fn update(&self, f: impl FnOnce(&mut MicGateSettings)) {
+        f(&mut self.settings.lock().unwrap());
+    }
+
+    pub fn is_speaking(&self) -> bool {
+        self.speaking.load(Ordering::Relaxed)
+    }
+
+    /// Stores the detector state, returning true if it changed.
+    pub fn set_speaking(&self, speaking: bool) -> bool {
+        self.speaking.swap(speaking, Ordering::Relaxed) != speaking
+    }
+}
+
+/// Energy-based voice activity detector with attack and hangover.
+#[derive(Default)]
+pub struct VoiceActivityDetector {
+    speaking: bool,
+    above_ms: u32,
+    below_ms: u32,
+}
+
+impl VoiceActivityDetector {
+    /// Feeds the linear RMS level of one frame of `frame_ms` and returns
+    /// whether speech is active.
+    pub fn process(&mut self, rms: f32, frame_ms: u32, config: &VadConfig) -> bool {
+        if to_db(rms) >= config.threshold_db {
+            self.above_ms += frame_ms;
+            self.below_ms = 0;
+            if self.above_ms >= config.attack_ms {
+                self.speaking = true;
+            }
+        } else {
+            self.below_ms += frame_ms;
+            self.above_ms = 0;
+            if self.below_ms > config.release_ms {
+                self.speaking = false;
+            }
+        }
+        self.speaking
+    }
+}
+
+/// Linear level in dBFS, -100 for silence.
+fn to_db(level: f32) -> f32 {
+    if level <= 1e-5 {
+        -100.0
+    } else {
+        20.0 * level.log10()
+    }
+}